@@ -1,5 +1,56 @@
-from .error import Error
+from .connect import (
+    Transport,
+    build_connection_string,
+    transport_attributes,
+    validate_connection_string,
+)
+from .environment import Environment
+from .error import (
+    Error,
+    ConnectionFailure,
+    ConstraintViolation,
+    Timeout,
+    Deadlock,
+    SyntaxOrAccessError,
+    DataError,
+)
+from .info import column_defaults, max_column_name_len
+from .parameter import DEFAULT, TypedNull
+from .pool import (
+    enable_odbc_connection_pooling,
+    disable_odbc_connection_pooling,
+    set_odbc_connection_pool_matching,
+)
+from .prepared import PreparedStatement, prepare
 from .reader import BatchReader, read_arrow_batches_from_odbc
-from .writer import insert_into_table
+from .statement import execute
+from .writer import execute_many, insert_into_table
 
-__all__ = ["BatchReader", "read_arrow_batches_from_odbc", "Error", "insert_into_table"]
+__all__ = [
+    "BatchReader",
+    "Environment",
+    "read_arrow_batches_from_odbc",
+    "Error",
+    "ConnectionFailure",
+    "ConstraintViolation",
+    "Timeout",
+    "Deadlock",
+    "SyntaxOrAccessError",
+    "DataError",
+    "insert_into_table",
+    "execute_many",
+    "execute",
+    "prepare",
+    "PreparedStatement",
+    "DEFAULT",
+    "TypedNull",
+    "enable_odbc_connection_pooling",
+    "disable_odbc_connection_pooling",
+    "set_odbc_connection_pool_matching",
+    "max_column_name_len",
+    "column_defaults",
+    "Transport",
+    "transport_attributes",
+    "build_connection_string",
+    "validate_connection_string",
+]
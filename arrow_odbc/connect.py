@@ -1,9 +1,11 @@
-from typing import Any, Optional, Tuple
+from enum import IntEnum
+from typing import Any, Callable, Dict, Optional, Tuple
 from cffi.api import FFI  # type: ignore
 
 from pyarrow.cffi import ffi as arrow_ffi  # type: ignore
 
 from ._native import ffi, lib  # type: ignore
+from arrow_odbc.environment import Environment
 from arrow_odbc.error import raise_on_error
 
 
@@ -13,31 +15,154 @@ def to_bytes_and_len(value: Optional[str]) -> Tuple[bytes, int]:
         value_len = 0
     else:
         value_bytes = value.encode("utf-8")
-        value_len = len(value)
+        value_len = len(value_bytes)
 
     return (value_bytes, value_len)
 
 
-def connect_to_database(connection_string, user, password) -> Any:
+def connect_to_database(
+    connection_string,
+    user,
+    password,
+    connect_deadline_sec: Optional[float] = None,
+    environment: Optional[Environment] = None,
+) -> Any:
 
     connection_string_bytes = connection_string.encode("utf-8")
 
     (user_bytes, user_len) = to_bytes_and_len(user)
     (password_bytes, password_len) = to_bytes_and_len(password)
 
+    if connect_deadline_sec is None:
+        connect_deadline_ms = 0
+    else:
+        # Round up, so a tiny deadline is not mistaken for no deadline at all.
+        connect_deadline_ms = max(1, int(connect_deadline_sec * 1000))
+
+    # `NULL` uses the environment shared by default
+    environment_handle = ffi.NULL if environment is None else environment.handle
+
     connection_out = ffi.new("OdbcConnection **")
 
     # Open connection to ODBC Data Source
     error = lib.arrow_odbc_connect_with_connection_string(
+        environment_handle,
         connection_string_bytes,
         len(connection_string_bytes),
         user_bytes,
         user_len,
         password_bytes,
         password_len,
+        connect_deadline_ms,
         connection_out,
     )
     # See if we connected successfully and return an error if not
     raise_on_error(error)
     # Dereference output pointer. This gives us an `OdbcConnection *`
-    return connection_out[0]
\ No newline at end of file
+    return connection_out[0]
+
+
+def validate_connection_string(connection_string: str):
+    """
+    Checks the syntax of the connection string and that the driver or data source it refers to is
+    known to the ODBC driver manager. In contrast to connecting, this does not require the data
+    source to be reachable, which makes it suitable to check configurations e.g. in CI.
+
+    :param connection_string: ODBC Connection string to check.
+    :raises Error: If the connection string is malformed, or refers to an unknown driver or data
+        source.
+    """
+    connection_string_bytes = connection_string.encode("utf-8")
+    error = lib.arrow_odbc_validate_connection_string(
+        connection_string_bytes, len(connection_string_bytes)
+    )
+    raise_on_error(error)
+
+
+class Transport(IntEnum):
+    """
+    Transport used to reach the data source. As the connection string attributes selecting it are
+    specific to the driver, each transport is tied to a driver.
+    """
+
+    SQL_SERVER_TCP = 0
+    """SQL Server over TCP/IP. Target is ``host[,port]``."""
+    SQL_SERVER_NAMED_PIPE = 1
+    """SQL Server over named pipes. Target is the pipe, e.g. ``\\\\.\\pipe\\sql\\query``."""
+    SQL_SERVER_SHARED_MEMORY = 2
+    """SQL Server over shared memory. Target is the local instance, e.g. ``.``."""
+    POSTGRES_UNIX_SOCKET = 3
+    """PostgreSQL over a Unix domain socket. Target is the directory holding the socket."""
+    MYSQL_UNIX_SOCKET = 4
+    """MySQL or MariaDB over a Unix domain socket. Target is the path of the socket."""
+
+
+def transport_attributes(transport: Transport, target: str) -> str:
+    """
+    Connection string attributes selecting a transport like named pipes or Unix domain sockets,
+    which are often faster for local connections. The value is escaped, so the result can be
+    appended to a connection string, e.g.
+    ``"Driver={ODBC Driver 17 for SQL Server};" + transport_attributes(...)``.
+
+    :param transport: Transport to use. Determines the attribute and the format of ``target``.
+    :param target: Server, pipe or socket to connect to, depending on ``transport``.
+    :return: Attributes to append to the connection string, including a trailing ``;``.
+    """
+    target_bytes = target.encode("utf-8")
+    return _text_from_native(
+        lambda buf, capacity, len_out: lib.arrow_odbc_transport_attributes(
+            transport, target_bytes, len(target_bytes), buf, capacity, len_out
+        )
+    )
+
+
+def build_connection_string(template: str, values: Dict[str, str]) -> str:
+    """
+    Builds a connection string from a template, e.g. read from a configuration file. Each ``{key}``
+    placeholder is replaced with the value for ``key``. Values are escaped, so they can not inject
+    further attributes, even if they contain characters like ``;``. Braces not enclosing one of the
+    keys, like in ``Driver={ODBC Driver 17 for SQL Server}``, are left untouched.
+
+    :param template: Connection string with placeholders, e.g. ``Server={host};PWD={password};``.
+        Placeholders should make up the entire value of an attribute, since the value is escaped
+        as a whole.
+    :param values: Maps the keys of the placeholders to their values.
+    :return: Connection string with all placeholders of known keys replaced.
+    """
+    template_bytes = template.encode("utf-8")
+    keys = [key.encode("utf-8") for key in values.keys()]
+    texts = [value.encode("utf-8") for value in values.values()]
+    # Rust copies the text, so the buffers only need to outlive this call.
+    key_bufs = ffi.new("uint8_t *[]", [ffi.from_buffer(key) for key in keys])
+    value_bufs = ffi.new("uint8_t *[]", [ffi.from_buffer(text) for text in texts])
+    return _text_from_native(
+        lambda buf, capacity, len_out: lib.arrow_odbc_build_connection_string(
+            template_bytes,
+            len(template_bytes),
+            key_bufs,
+            [len(key) for key in keys],
+            value_bufs,
+            [len(text) for text in texts],
+            len(keys),
+            buf,
+            capacity,
+            len_out,
+        )
+    )
+
+
+def _text_from_native(fill: Callable[[Any, int, Any], Any]) -> str:
+    """
+    Retrieves text from a native function filling a caller provided buffer. ``fill`` is invoked
+    with the buffer, its capacity and a pointer receiving the required length. It is invoked again
+    with a larger buffer, should the first one have been too small.
+    """
+    capacity = 256
+    len_out = ffi.new("uintptr_t *")
+    while True:
+        buf = ffi.new("uint8_t[]", capacity)
+        error = fill(buf, capacity, len_out)
+        raise_on_error(error)
+        if len_out[0] <= capacity:
+            return ffi.buffer(buf, len_out[0])[:].decode("utf-8")
+        capacity = len_out[0]
\ No newline at end of file
@@ -0,0 +1,26 @@
+from ._native import ffi, lib  # type: ignore
+from .error import raise_on_error
+
+
+class Environment:
+    """
+    An ODBC environment of its own. By default all connections are opened using a single
+    environment shared by the entire process. Passing an ``Environment`` instead isolates the
+    connections opened with it, e.g. in tests.
+
+    Connections, readers, writers and streams created with it keep it alive, so it is only
+    deallocated once the last of them has been freed.
+    """
+
+    def __init__(self):
+        self.handle = None
+        environment_out = ffi.new("ArrowOdbcEnvironment **")
+        error = lib.arrow_odbc_environment_make(environment_out)
+        raise_on_error(error)
+        self.handle = environment_out[0]
+
+    def __del__(self):
+        # Free the resources associated with this handle. The handle is `None` if allocating the
+        # environment failed.
+        if self.handle is not None:
+            lib.arrow_odbc_environment_free(self.handle)
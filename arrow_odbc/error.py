@@ -1,3 +1,5 @@
+from typing import Optional
+
 from ._native import lib, ffi  # type: ignore
 
 
@@ -18,13 +20,78 @@ class Error(Exception):
         """
         return ffi.string(lib.arrow_odbc_error_message(self.handle)).decode("utf-8")
 
+    def sql_state(self) -> Optional[str]:
+        """
+        Five character SQLSTATE reported by the ODBC driver, e.g. ``"23000"``. ``None`` if the error
+        does not originate from a diagnostic record of the driver.
+        """
+        sql_state = lib.arrow_odbc_error_sql_state(self.handle)
+        if sql_state == ffi.NULL:
+            return None
+        return ffi.string(sql_state).decode("ascii")
+
     def __str__(self) -> str:
         return self.message()
 
 
+class ConnectionFailure(Error):
+    """
+    The connection to the data source could not be established or has been lost (SQLSTATE class
+    ``08``).
+    """
+
+
+class ConstraintViolation(Error):
+    """
+    An integrity constraint, like a primary or foreign key, has been violated (SQLSTATE class
+    ``23``).
+    """
+
+
+class Timeout(Error):
+    """
+    The operation timed out (SQLSTATE ``HYT00`` or ``HYT01``).
+    """
+
+
+class Deadlock(Error):
+    """
+    The transaction has been rolled back, e.g. due to a deadlock or serialization failure (SQLSTATE
+    class ``40``).
+    """
+
+
+class SyntaxOrAccessError(Error):
+    """
+    The statement is malformed or refers to objects which do not exist or can not be accessed
+    (SQLSTATE class ``42``).
+    """
+
+
+class DataError(Error):
+    """
+    A value could not be processed, e.g. it is out of range or can not be converted (SQLSTATE class
+    ``22``).
+    """
+
+
+# Indexed by the category returned by `arrow_odbc_error_category`.
+_ERROR_BY_CATEGORY = [
+    Error,
+    ConnectionFailure,
+    ConstraintViolation,
+    Timeout,
+    Deadlock,
+    SyntaxOrAccessError,
+    DataError,
+]
+
+
 def raise_on_error(error_out):
     """
-    Raises if the argument points to an error
+    Raises if the argument points to an error. The type of the exception raised depends on the
+    SQLSTATE reported by the driver.
     """
     if error_out != ffi.NULL:
-        raise Error(error_out)
+        category = lib.arrow_odbc_error_category(error_out)
+        raise _ERROR_BY_CATEGORY[category](error_out)
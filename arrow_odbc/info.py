@@ -0,0 +1,112 @@
+from typing import Dict, Optional
+
+from pyarrow import Array, RecordBatch  # type: ignore
+from pyarrow.cffi import ffi as arrow_ffi  # type: ignore
+
+from .connect import connect_to_database, to_bytes_and_len
+from ._native import ffi, lib  # type: ignore
+from .environment import Environment
+from .error import raise_on_error
+
+
+def max_column_name_len(
+    connection_string: str,
+    user: Optional[str] = None,
+    password: Optional[str] = None,
+    environment: Optional[Environment] = None,
+) -> Optional[int]:
+    """
+    Maximum length of a column name supported by the data source. Use it to validate or truncate
+    column names before generating a table for ``insert_into_table``.
+
+    :param connection_string: ODBC Connection string used to connect to the data source. To find a
+        connection string for your data source try https://www.connectionstrings.com/.
+    :param user: Allows for specifying the user seperatly from the connection string if it is not
+        already part of it. The value will eventually be escaped and attached to the connection
+        string as `UID`.
+    :param password: Allows for specifying the password seperatly from the connection string if it
+        is not already part of it. The value will eventually be escaped and attached to the
+        connection string as `PWD`.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :return: Maximum number of characters in a column name. ``None`` if there is no limit, or the
+        driver does not know it.
+    """
+    connection = connect_to_database(
+        connection_string, user, password, environment=environment
+    )
+
+    max_len_out = ffi.new("uint16_t *")
+    error = lib.arrow_odbc_connection_max_column_name_len(connection, max_len_out)
+    lib.arrow_odbc_connection_free(connection)
+    raise_on_error(error)
+
+    max_len = max_len_out[0]
+    if max_len == 0:
+        return None
+    else:
+        return max_len
+
+
+def column_defaults(
+    table: str,
+    connection_string: str,
+    user: Optional[str] = None,
+    password: Optional[str] = None,
+    catalog: Optional[str] = None,
+    schema: Optional[str] = None,
+    environment: Optional[Environment] = None,
+) -> Dict[str, Optional[str]]:
+    """
+    Default value definitions of the columns of a table, as reported by the driver. Use them to
+    replicate the defaults of a source table, e.g. before generating a table for
+    ``insert_into_table``.
+
+    :param table: Name of the table.
+    :param connection_string: ODBC Connection string used to connect to the data source. To find a
+        connection string for your data source try https://www.connectionstrings.com/.
+    :param user: Allows for specifying the user seperatly from the connection string if it is not
+        already part of it. The value will eventually be escaped and attached to the connection
+        string as `UID`.
+    :param password: Allows for specifying the password seperatly from the connection string if it
+        is not already part of it. The value will eventually be escaped and attached to the
+        connection string as `PWD`.
+    :param catalog: Catalog (i.e. database) of the table. ``None`` uses the current catalog of the
+        connection.
+    :param schema: Schema of the table. ``None`` matches tables in any schema, so pass it if tables
+        of the same name exist in several schemas.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :return: Maps each column name to the SQL expression of its default value, e.g. ``"((42))"``
+        for SQL Server, or ``None`` if the column has no default. The expressions are passed on as
+        reported by the driver, so their syntax is specific to the data source. Empty if the table
+        does not exist.
+    """
+    (catalog_bytes, catalog_len) = to_bytes_and_len(catalog)
+    (schema_bytes, schema_len) = to_bytes_and_len(schema)
+    (table_bytes, table_len) = to_bytes_and_len(table)
+
+    connection = connect_to_database(
+        connection_string, user, password, environment=environment
+    )
+
+    array = arrow_ffi.new("struct ArrowArray *")
+    arrow_schema = arrow_ffi.new("struct ArrowSchema *")
+    error = lib.arrow_odbc_connection_column_defaults(
+        connection,
+        catalog_bytes,
+        catalog_len,
+        schema_bytes,
+        schema_len,
+        table_bytes,
+        table_len,
+        array,
+        arrow_schema,
+    )
+    lib.arrow_odbc_connection_free(connection)
+    raise_on_error(error)
+
+    array_ptr = int(ffi.cast("uintptr_t", array))
+    schema_ptr = int(ffi.cast("uintptr_t", arrow_schema))
+    batch = RecordBatch.from_struct_array(Array._import_from_c(array_ptr, schema_ptr))
+    return dict(zip(batch.column(0).to_pylist(), batch.column(1).to_pylist()))
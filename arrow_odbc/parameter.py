@@ -0,0 +1,158 @@
+from datetime import date, datetime
+from decimal import Decimal
+from typing import Any, List, Optional, Tuple
+from cffi.api import FFI  # type: ignore
+
+from ._native import ffi, lib  # type: ignore
+from .connect import to_bytes_and_len
+from .error import raise_on_error
+
+
+class _Default:
+    def __repr__(self) -> str:
+        return "DEFAULT"
+
+
+DEFAULT = _Default()
+"""
+Pass ``DEFAULT`` as a parameter to let the column fall back to its default value, e.g. in an
+``INSERT`` statement. Not all drivers support this, in which case executing the statement raises
+an ``Error``.
+"""
+
+
+class TypedNull:
+    """
+    A ``NULL`` parameter of a specific SQL type. Use it instead of ``None`` for drivers, e.g. for
+    Oracle, which require the type of a ``NULL`` to match the column it is compared to or inserted
+    into. ``None`` is passed as ``NULL`` of type ``VARCHAR``.
+
+    :param sql_data_type: Code of the SQL type as defined by ODBC, e.g. ``12`` for ``SQL_VARCHAR``,
+        ``4`` for ``SQL_INTEGER``, ``-5`` for ``SQL_BIGINT``, ``8`` for ``SQL_DOUBLE``, ``91`` for
+        ``SQL_TYPE_DATE`` or ``93`` for ``SQL_TYPE_TIMESTAMP``.
+    """
+
+    def __init__(self, sql_data_type: int):
+        self.sql_data_type = sql_data_type
+
+    def __repr__(self) -> str:
+        return f"TypedNull({self.sql_data_type})"
+
+
+_I64_MIN = -(2**63)
+_I64_MAX = 2**63 - 1
+_MAX_DECIMAL_PRECISION = 38
+
+
+def check_parameters(parameters: Optional[List[Any]]):
+    """
+    Raises a ``TypeError`` if any of the parameters can not be bound, or a ``ValueError`` for
+    decimals which do not fit into ``NUMERIC``. Call this before acquiring resources like
+    connections, so a wrong argument can not leak them.
+    """
+    if parameters is None:
+        return
+    for p_index, parameter in enumerate(parameters):
+        if not _is_supported(parameter):
+            raise TypeError(
+                f"Parameter {p_index} of type {type(parameter).__name__} is not supported. "
+                "Supported are str, bytes, bool, int, float, Decimal, date, memoryview, None, "
+                "TypedNull and DEFAULT."
+            )
+        if isinstance(parameter, Decimal):
+            _check_decimal(p_index, parameter)
+
+
+def to_parameter_array(parameters: Optional[List[Any]]) -> Tuple[Any, int]:
+    """
+    Creates an array of ``ArrowOdbcParameter`` handles from the parameters. Rust takes ownership of
+    the handles, then the array is passed to it. So this should only be called once nothing can
+    fail anymore before handing over the array. Parameters must have been checked with
+    ``check_parameters``. The types of the elements may differ from each other.
+    """
+    if parameters is None:
+        return (FFI.NULL, 0)
+
+    parameters_array = ffi.new("ArrowOdbcParameter *[]", len(parameters))
+    for p_index, parameter in enumerate(parameters):
+        parameters_array[p_index] = _make_parameter(parameter)
+    return (parameters_array, len(parameters))
+
+
+def _is_supported(parameter: Any) -> bool:
+    return (
+        parameter is None
+        or parameter is DEFAULT
+        or isinstance(parameter, TypedNull)
+        or isinstance(parameter, (str, bytes, bytearray, int, float, Decimal, memoryview))
+        # `datetime` derives from `date`, yet binding it as a date would lose the time.
+        or (isinstance(parameter, date) and not isinstance(parameter, datetime))
+    )
+
+
+def _check_decimal(p_index: int, parameter: Decimal):
+    if not parameter.is_finite():
+        raise ValueError(f"Parameter {p_index} is {parameter}, which can not be bound as NUMERIC.")
+    (mantissa, scale) = _mantissa_and_scale(parameter)
+    precision = max(len(mantissa.lstrip("-").lstrip("0")), scale, 1)
+    if precision > _MAX_DECIMAL_PRECISION:
+        raise ValueError(
+            f"Parameter {p_index} has a precision of {precision}, which exceeds the maximum of "
+            f"{_MAX_DECIMAL_PRECISION} supported for NUMERIC."
+        )
+
+
+def _mantissa_and_scale(parameter: Decimal) -> Tuple[str, int]:
+    """
+    Decimal digits of the mantissa, with a leading ``-`` for negative values, and the scale.
+    """
+    (sign, digits, exponent) = parameter.as_tuple()
+    mantissa = "".join(str(digit) for digit in digits)
+    # A positive exponent, like in ``Decimal("1E+3")``, is moved into the mantissa.
+    if exponent > 0:
+        mantissa += "0" * exponent
+        exponent = 0
+    if sign:
+        mantissa = "-" + mantissa
+    return (mantissa, -exponent)
+
+
+def _make_parameter(parameter: Any) -> Any:
+    if parameter is DEFAULT:
+        return lib.arrow_odbc_parameter_default_make()
+    if isinstance(parameter, TypedNull):
+        return lib.arrow_odbc_parameter_null_make(parameter.sql_data_type)
+    # `bool` derives from `int`, so it must be checked first.
+    if isinstance(parameter, bool):
+        return lib.arrow_odbc_parameter_bool_make(parameter, False)
+    if isinstance(parameter, int):
+        if _I64_MIN <= parameter <= _I64_MAX:
+            return lib.arrow_odbc_parameter_i64_make(parameter, False)
+        # Too large for BIGINT. Let the driver convert the text, e.g. into a DECIMAL.
+        parameter = str(parameter)
+    if isinstance(parameter, float):
+        return lib.arrow_odbc_parameter_f64_make(parameter, False)
+    if isinstance(parameter, Decimal):
+        (mantissa, scale) = _mantissa_and_scale(parameter)
+        (m_bytes, m_len) = to_bytes_and_len(mantissa)
+        parameter_out = ffi.new("ArrowOdbcParameter **")
+        # Can not fail, since the decimal has been checked by `check_parameters`.
+        error = lib.arrow_odbc_parameter_decimal_make(m_bytes, m_len, scale, parameter_out)
+        raise_on_error(error)
+        return parameter_out[0]
+    if isinstance(parameter, (bytes, bytearray)):
+        # Rust copies the bytes, so the buffer only needs to outlive this call.
+        buf = ffi.from_buffer(parameter)
+        return lib.arrow_odbc_parameter_binary_make(buf, len(buf), False)
+    if isinstance(parameter, memoryview):
+        # The memoryview holds on to the underlying buffer, so the bytes stay valid as long as the
+        # caller holds the list of parameters, i.e. until the statement has been executed.
+        buf = ffi.from_buffer(parameter)
+        return lib.arrow_odbc_parameter_binary_borrowed_make(buf, len(buf))
+    if isinstance(parameter, date):
+        return lib.arrow_odbc_parameter_date_make(
+            parameter.year, parameter.month, parameter.day, False
+        )
+    # Rust copies the text, so the encoded bytes only need to outlive this call.
+    (p_bytes, p_len) = to_bytes_and_len(parameter)
+    return lib.arrow_odbc_parameter_string_make(p_bytes, p_len)
@@ -0,0 +1,37 @@
+from ._native import lib  # type: ignore
+from .error import raise_on_error
+
+
+def enable_odbc_connection_pooling():
+    """
+    Reduce overhead of opening connections by reusing them. Pooling is driver aware, i.e. each
+    driver manages its own pool of connections. Call this before opening the first connection
+    in the process. Changing the pooling setting after connections have been opened is undefined
+    behaviour according to the ODBC standard.
+    """
+    error = lib.arrow_odbc_enable_connection_pooling()
+    raise_on_error(error)
+
+
+def disable_odbc_connection_pooling():
+    """
+    Switches connection pooling off again. Off is the default. Just like
+    ``enable_odbc_connection_pooling`` this should be called before opening the first connection
+    in the process, e.g. to measure unpooled behaviour.
+    """
+    error = lib.arrow_odbc_disable_connection_pooling()
+    raise_on_error(error)
+
+
+def set_odbc_connection_pool_matching(strict: bool):
+    """
+    Controls how closely connection attributes must match, in order for a pooled connection to be
+    reused.
+
+    :param strict: ``True`` (the default) only reuses connections with exactly matching connection
+        strings. ``False`` relaxes the matching and leaves it up to the driver.
+    :raises Error: If a connection has already been opened in this process. Just like enabling
+        pooling, this must be configured before that.
+    """
+    error = lib.arrow_odbc_set_cp_match(strict)
+    raise_on_error(error)
@@ -0,0 +1,143 @@
+from typing import Any, List, Optional
+
+from .connect import connect_to_database
+from ._native import ffi, lib  # type: ignore
+from .environment import Environment
+from .error import raise_on_error
+from .parameter import check_parameters, to_parameter_array
+from .reader import BatchReader
+
+
+class PreparedStatement:
+    """
+    A statement which has been prepared once and can be executed repeatedly with different
+    parameters. Created by ``prepare``.
+    """
+
+    def __init__(self, handle):
+        """
+        Low level constructor, users should rather invoke ``prepare`` in order to create instances
+        of ``PreparedStatement``.
+        """
+        self.handle = handle
+
+    def __del__(self):
+        # A reader of the last execution still alive keeps the statement open until it is freed.
+        lib.arrow_odbc_prepared_statement_free(self.handle)
+
+    def execute(
+        self,
+        batch_size: int,
+        parameters: Optional[List[Any]] = None,
+        max_text_size: Optional[int] = None,
+        max_binary_size: Optional[int] = None,
+        falliable_allocations: bool = True,
+        decimal_as_text: bool = False,
+        bit_as_boolean: bool = True,
+        raw_binary_columns: Optional[List[int]] = None,
+    ) -> Optional[BatchReader]:
+        """
+        Executes the statement with the parameters passed. The reader returned by the previous
+        execution, or a stream it has been converted into, owns the statement until it is garbage
+        collected. Executing the statement while it is still alive raises an ``Error``, so ``del``
+        it first.
+
+        :param batch_size: The maximum number rows within each batch.
+        :param parameters: Values for the placeholders (``?``) of the statement. Supports the same
+            types as ``read_arrow_batches_from_odbc``.
+        :param max_text_size: See ``read_arrow_batches_from_odbc``.
+        :param max_binary_size: See ``read_arrow_batches_from_odbc``.
+        :param falliable_allocations: See ``read_arrow_batches_from_odbc``.
+        :param decimal_as_text: See ``read_arrow_batches_from_odbc``.
+        :param bit_as_boolean: See ``read_arrow_batches_from_odbc``.
+        :param raw_binary_columns: See ``read_arrow_batches_from_odbc``.
+        :return: ``None`` if the statement does not produce a result set. Otherwise a
+            ``BatchReader`` iterating over the result set.
+        """
+        check_parameters(parameters)
+
+        if max_text_size is None:
+            max_text_size = 0
+
+        if max_binary_size is None:
+            max_binary_size = 0
+
+        if raw_binary_columns is None:
+            raw_binary_array = ffi.NULL
+            raw_binary_len = 0
+        else:
+            raw_binary_array = ffi.new("uintptr_t[]", raw_binary_columns)
+            raw_binary_len = len(raw_binary_columns)
+
+        (parameters_array, parameters_len) = to_parameter_array(parameters)
+
+        reader_out = ffi.new("ArrowOdbcReader **")
+        error = lib.arrow_odbc_prepared_execute(
+            self.handle,
+            parameters_array,
+            parameters_len,
+            batch_size,
+            max_text_size,
+            max_binary_size,
+            falliable_allocations,
+            decimal_as_text,
+            bit_as_boolean,
+            raw_binary_array,
+            raw_binary_len,
+            reader_out,
+        )
+        raise_on_error(error)
+
+        reader = reader_out[0]
+        if reader == ffi.NULL:
+            # The statement ran successfully but did not produce a result set
+            return None
+
+        return BatchReader(reader)
+
+
+def prepare(
+    query: str,
+    connection_string: str,
+    user: Optional[str] = None,
+    password: Optional[str] = None,
+    connect_deadline_sec: Optional[float] = None,
+    environment: Optional[Environment] = None,
+) -> PreparedStatement:
+    """
+    Prepares a statement, so the data source parses and plans it only once. Use this over
+    ``read_arrow_batches_from_odbc`` to issue the same parameterized query many times.
+
+    :param query: The SQL statement to prepare. Use question marks (``?``) as placeholders for
+        parameters.
+    :param connection_string: ODBC Connection string used to connect to the data source. To find a
+        connection string for your data source try https://www.connectionstrings.com/.
+    :param user: Allows for specifying the user seperatly from the connection string if it is not
+        already part of it. The value will eventually be escaped and attached to the connection
+        string as `UID`.
+    :param password: Allows for specifying the password seperatly from the connection string if it
+        is not already part of it. The value will eventually be escaped and attached to the
+        connection string as `PWD`.
+    :param connect_deadline_sec: Maximum time in seconds to wait for the connection to the data
+        source to be established, before an ``Error`` is raised. In contrast to a login timeout this
+        does not depend on the driver honoring it. ``None`` waits indefinitely.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :return: The prepared statement. It owns the connection, which is closed once the statement is
+        garbage collected.
+    """
+    query_bytes = query.encode("utf-8")
+
+    connection = connect_to_database(
+        connection_string, user, password, connect_deadline_sec, environment
+    )
+
+    # `arrow_odbc_connection_prepare` takes ownership of the connection. Even if it should fail the
+    # connection will be closed.
+    statement_out = ffi.new("ArrowOdbcPreparedStatement **")
+    error = lib.arrow_odbc_connection_prepare(
+        connection, query_bytes, len(query_bytes), statement_out
+    )
+    raise_on_error(error)
+
+    return PreparedStatement(statement_out[0])
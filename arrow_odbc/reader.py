@@ -1,13 +1,29 @@
-from typing import List, Optional, Tuple
-from cffi.api import FFI  # type: ignore
+import os
+
+from itertools import count
+from time import sleep
+from typing import Any, Callable, Dict, List, Optional, Tuple, Union
 
 from pyarrow.cffi import ffi as arrow_ffi  # type: ignore
-from pyarrow import RecordBatch, Schema, Array
+from pyarrow import (
+    RecordBatch,
+    RecordBatchReader,
+    Schema,
+    Array,
+    Table,
+    DataType,
+    float32,
+    float64,
+    ipc,
+    memory_map,
+)
 
-from arrow_odbc.connect import to_bytes_and_len, connect_to_database  # type: ignore
+from arrow_odbc.connect import connect_to_database, to_bytes_and_len  # type: ignore
 
 from ._native import ffi, lib  # type: ignore
-from .error import raise_on_error
+from .environment import Environment
+from .error import Error, raise_on_error
+from .parameter import check_parameters, to_parameter_array
 
 
 class BatchReader:
@@ -25,7 +41,11 @@ class BatchReader:
         # We take owners of the corresponding reader written in Rust and keep it alive until `self`
         # is deleted
         self.handle = handle
-        # Expose schema as attribute
+        self._update_schema()
+
+    def _update_schema(self):
+        # Expose schema as attribute. Must be called again, after configuring transformations which
+        # alter the schema.
         # https://github.com/apache/arrow/blob/5ead37593472c42f61c76396dde7dcb8954bde70/python/pyarrow/tests/test_cffi.py
         schema_out = arrow_ffi.new("struct ArrowSchema *")
         error = lib.arrow_odbc_reader_schema(self.handle, schema_out)
@@ -34,8 +54,10 @@ class BatchReader:
         self.schema = Schema._import_from_c(ptr_schema)
 
     def __del__(self):
-        # Free the resources associated with this handle.
-        lib.arrow_odbc_reader_free(self.handle)
+        # Free the resources associated with this handle. The handle is `None` if ownership has
+        # been transferred to a stream.
+        if self.handle is not None:
+            lib.arrow_odbc_reader_free(self.handle)
 
     def __iter__(self):
         # Implement iterable protocol so reader can be used in for loops.
@@ -43,23 +65,223 @@ class BatchReader:
 
     def __next__(self) -> RecordBatch:
         # Implment iterator protocol
+        batch = self._next(has_more_out=ffi.NULL)
+        if batch is None:
+            raise StopIteration()
+        return batch
+
+    def next_and_is_last(self) -> Optional[Tuple[RecordBatch, bool]]:
+        """
+        Fetches the next batch along with whether it is the last one, so e.g. final structures can
+        be allocated in time. To find out, the following batch is fetched ahead, which delays
+        handing out the current one. An error fetching ahead is raised by the next call, rather
+        than this one. May be mixed with iterating the reader.
+
+        :return: The next batch and ``True`` if no further batches follow. ``None`` if the result
+            set is exhausted.
+        """
+        has_more_out = ffi.new("int*")
+        batch = self._next(has_more_out)
+        if batch is None:
+            return None
+        return (batch, has_more_out[0] == 0)
 
+    def _next(self, has_more_out) -> Optional[RecordBatch]:
         # In case of an error this is going to be a non null handle to the error
         array = arrow_ffi.new("struct ArrowArray *")
         schema = arrow_ffi.new("struct ArrowSchema *")
 
         has_next_out = ffi.new("int*")
 
-        error = lib.arrow_odbc_reader_next(self.handle, array, schema, has_next_out)
+        error = lib.arrow_odbc_reader_next(self.handle, array, schema, has_next_out, has_more_out)
         raise_on_error(error)
 
         if has_next_out[0] == 0:
-            raise StopIteration()
+            return None
         else:
             array_ptr = int(ffi.cast("uintptr_t", array))
             schema_ptr = int(ffi.cast("uintptr_t", schema))
             struct_array = Array._import_from_c(array_ptr, schema_ptr)
-            return RecordBatch.from_struct_array(struct_array)
+            batch = RecordBatch.from_struct_array(struct_array)
+            # Schema level metadata does not survive the detour over a struct array.
+            if self.schema.metadata:
+                batch = batch.replace_schema_metadata(self.schema.metadata)
+            return batch
+
+    def into_pyarrow_record_batch_reader(self) -> RecordBatchReader:
+        """
+        Converts this reader into a ``pyarrow.RecordBatchReader`` using the Arrow C stream
+        interface. The stream always carries the schema of the result set, so an empty result set
+        yields a reader with the correct schema and zero batches. This consumes the ``BatchReader``,
+        which must not be used afterwards.
+        """
+        stream_out = arrow_ffi.new("struct ArrowArrayStream *")
+        lib.arrow_odbc_reader_into_stream(self.handle, stream_out)
+        # Ownership of the Rust reader has been transferred to the stream
+        self.handle = None
+        stream_ptr = int(ffi.cast("uintptr_t", stream_out))
+        return RecordBatchReader._import_from_c(stream_ptr)
+
+    def __arrow_c_stream__(self, requested_schema=None):
+        """
+        Implements the Arrow PyCapsule interface, so the reader can be passed directly to libraries
+        consuming Arrow streams, e.g. ``pyarrow.table(reader)``. Like
+        ``into_pyarrow_record_batch_reader`` this consumes the ``BatchReader``, which must not be
+        used afterwards. Requires ``pyarrow >= 14``.
+
+        :param requested_schema: Schema the consumer asks for, as a PyCapsule. If it differs from
+            ``schema``, each batch is cast to it while streaming. Raises if the columns can not be
+            cast.
+        :return: PyCapsule named ``arrow_array_stream``, owning the stream. It is released once the
+            capsule is garbage collected, unless a consumer took ownership of the stream.
+        """
+        reader = self.into_pyarrow_record_batch_reader()
+        if requested_schema is not None:
+            target = Schema._import_from_c_capsule(requested_schema)
+            if target != reader.schema:
+                reader = _cast_batches(reader, target)
+        # pyarrow takes care of creating the capsule and its destructor, so the stream is released
+        # exactly once, no matter whether the consumer moves it out of the capsule.
+        return reader.__arrow_c_stream__()
+
+    def read_all(self) -> Table:
+        """
+        Fetches all remaining batches and returns them as a single ``pyarrow.Table``. All batches
+        are handed over from Rust with one call, which avoids the per batch overhead of iterating
+        for small result sets.
+        """
+        stream_out = arrow_ffi.new("struct ArrowArrayStream *")
+        error = lib.arrow_odbc_reader_read_all(self.handle, stream_out)
+        raise_on_error(error)
+        stream_ptr = int(ffi.cast("uintptr_t", stream_out))
+        return RecordBatchReader._import_from_c(stream_ptr).read_all()
+
+    def drain(self, on_batch: Callable[[int], None]):
+        """
+        Fetches all remaining batches without converting them into Python objects. Useful if only
+        progress is of interest.
+
+        :param on_batch: Invoked with the number of rows of each fetched batch.
+        """
+
+        @ffi.callback("void(uintptr_t)")
+        def callback(num_rows):
+            on_batch(num_rows)
+
+        error = lib.arrow_odbc_reader_drain_with_callback(self.handle, callback)
+        raise_on_error(error)
+
+    def spill_to_dir(self, directory: str) -> RecordBatchReader:
+        """
+        Fetches all remaining batches and spills them to Arrow IPC files within ``directory``. Each
+        batch is written into its own file, so result sets larger than the available memory can be
+        extracted.
+
+        :param directory: Path to an existing directory the files are written into. Files are named
+            after the zero based index of the batch, e.g. ``0000000000.arrow``.
+        :return: A ``pyarrow.RecordBatchReader`` replaying the spilled batches. The files are memory
+            mapped one at a time while iterating.
+        """
+        directory_bytes = directory.encode("utf-8")
+        num_batches_out = ffi.new("uintptr_t *")
+
+        error = lib.arrow_odbc_reader_spill_to_dir(
+            self.handle, directory_bytes, len(directory_bytes), num_batches_out
+        )
+        raise_on_error(error)
+
+        paths = [
+            os.path.join(directory, f"{index:010}.arrow")
+            for index in range(num_batches_out[0])
+        ]
+
+        def replay():
+            for path in paths:
+                yield ipc.open_file(memory_map(path)).get_batch(0)
+
+        return RecordBatchReader.from_batches(self.schema, replay())
+
+    def to_parquet(self, path: str, row_group_size: int = 65536) -> int:
+        """
+        Fetches all remaining batches and writes them into a single parquet file. The batches are
+        written in Rust as they are fetched, without passing them through Python.
+
+        :param path: Path of the parquet file to create. An existing file is overwritten.
+        :param row_group_size: Maximum number of rows in each row group of the parquet file. Must be
+            at least 1.
+        :return: Number of rows written.
+        """
+        if row_group_size < 1:
+            raise ValueError("Row groups must hold at least 1 row.")
+        path_bytes = path.encode("utf-8")
+        num_rows_out = ffi.new("uintptr_t *")
+
+        error = lib.arrow_odbc_reader_to_parquet(
+            self.handle, path_bytes, len(path_bytes), row_group_size, num_rows_out
+        )
+        raise_on_error(error)
+
+        return num_rows_out[0]
+
+    def to_csv(self, path: str, delimiter: str = ",", header: bool = True) -> int:
+        """
+        Fetches all remaining batches and writes them into a CSV file. The batches are written in
+        Rust as they are fetched, without passing them through Python.
+
+        :param path: Path of the CSV file to create. An existing file is overwritten.
+        :param delimiter: Single ASCII character separating the fields.
+        :param header: If ``True`` the column names are written as first line. No header is written
+            for an empty result set.
+        :return: Number of rows written.
+        """
+        delimiter_bytes = delimiter.encode("ascii")
+        if len(delimiter_bytes) != 1:
+            raise ValueError("Delimiter must be a single ASCII character.")
+        path_bytes = path.encode("utf-8")
+        num_rows_out = ffi.new("uintptr_t *")
+
+        error = lib.arrow_odbc_reader_to_csv(
+            self.handle,
+            path_bytes,
+            len(path_bytes),
+            delimiter_bytes[0],
+            header,
+            num_rows_out,
+        )
+        raise_on_error(error)
+
+        return num_rows_out[0]
+
+
+_MASK_MODES = {"sha256": 0, "redact": 1}
+
+# Progress callbacks by the key passed to the Rust reader. An entry is removed once the reader, or a
+# stream it has been converted into, is freed, so the callback outlives every invocation.
+_progress_callbacks: Dict[int, Callable[[int], None]] = {}
+_progress_keys = count()
+
+
+@ffi.callback("void(uintptr_t, uintptr_t)")
+def _on_progress(key, total_rows):
+    _progress_callbacks[key](total_rows)
+
+
+@ffi.callback("void(uintptr_t)")
+def _release_progress(key):
+    del _progress_callbacks[key]
+
+
+def _cast_batches(reader: RecordBatchReader, schema: Schema) -> RecordBatchReader:
+    """
+    Casts the batches of ``reader`` lazily. pyarrow before version 17 does not cast streams to a
+    requested schema itself.
+    """
+    batches = (
+        cast
+        for batch in reader
+        for cast in Table.from_batches([batch]).cast(schema).to_batches()
+    )
+    return RecordBatchReader.from_batches(schema, batches)
 
 
 def read_arrow_batches_from_odbc(
@@ -68,10 +290,34 @@ def read_arrow_batches_from_odbc(
     connection_string: str,
     user: Optional[str] = None,
     password: Optional[str] = None,
-    parameters: Optional[List[Optional[str]]] = None,
+    parameters: Optional[List[Any]] = None,
     max_text_size: Optional[int] = None,
     max_binary_size: Optional[int] = None,
     falliable_allocations: bool = True,
+    partition_by: Optional[int] = None,
+    query_tag: Optional[str] = None,
+    decimal_as_text: bool = False,
+    bit_as_boolean: bool = True,
+    connect_deadline_sec: Optional[float] = None,
+    pair_columns_as_map: Optional[Tuple[int, int]] = None,
+    column_order: Optional[List[int]] = None,
+    parse_pg_arrays: Optional[Union[List[int], Dict[int, DataType]]] = None,
+    fixed_size_lists: Optional[Dict[int, Tuple[DataType, int]]] = None,
+    schema_metadata: Optional[Dict[str, str]] = None,
+    environment: Optional[Environment] = None,
+    column_names: Optional[List[Optional[str]]] = None,
+    char_as_bool: Optional[Dict[int, Tuple[str, str]]] = None,
+    empty_strings_as_null: bool = False,
+    raw_binary_columns: Optional[List[int]] = None,
+    max_batch_rows: Optional[int] = None,
+    downcast_integers: bool = False,
+    on_progress: Optional[Callable[[int], None]] = None,
+    progress_every_rows: int = 100000,
+    mask_columns: Optional[Dict[int, str]] = None,
+    large_lists: bool = False,
+    retry_sql_states: Optional[List[str]] = None,
+    max_retries: int = 3,
+    retry_backoff_sec: float = 0.1,
 ) -> Optional[BatchReader]:
     """
     Execute the query and read the result as an iterator over Arrow batches.
@@ -90,8 +336,13 @@ def read_arrow_batches_from_odbc(
     :param parameters: ODBC allows you to use a question mark as placeholder marker (``?``) for
         positional parameters. This argument takes a list of parameters those number must match the
         number of placholders in the SQL statement. Using this instead of literals helps you avoid
-        SQL injections or may otherwise simplify your code. Currently all parameters are passed as
-        VARCHAR strings. You can use `None` to pass `NULL`.
+        SQL injections or may otherwise simplify your code. Strings are passed as VARCHAR, ``bytes``
+        as VARBINARY, booleans as BIT, integers as BIGINT, floats as DOUBLE, ``decimal.Decimal`` as
+        NUMERIC and ``datetime.date`` as DATE. A ``memoryview`` is passed as VARBINARY without
+        copying its bytes, e.g. to upload a memory mapped file. You can use `None` to pass `NULL`,
+        ``TypedNull`` to pass `NULL` of a specific SQL type, or ``DEFAULT`` to let a column fall
+        back to its default value. Parameters of different kinds may be mixed within the same list.
+        A parameter of any other type raises a ``TypeError``.
     :param max_text_size: An upper limit for the size of buffers bound to variadic text columns of
         the data source. This limit does not (directly) apply to the size of the created arrow
         buffers, but rather applies to the buffers used for the data in transit. Use this option if
@@ -118,13 +369,292 @@ def read_arrow_batches_from_odbc(
         In case you can test your query against the schema you can safely set this to ``False``. The
         required memory will not depend on the amount of data in the data source. Default is
         ``True`` though, safety first.
+    :param partition_by: Zero based index of a column. If specified, each batch fetched from the
+        data source is split into one batch per distinct value of this column. Partitioning happens
+        within each fetched batch only, so the same value may appear in several batches, if it
+        spans the boundary of a fetch.
+    :param query_tag: Text prepended to the query as an SQL comment (``/* query_tag */``). This
+        makes it easier to identify the statement in the monitoring of the database, e.g. to
+        attribute load to the job which issued it. ``None`` means the query is sent unaltered.
+    :param decimal_as_text: If ``True`` decimal and numeric columns are read as strings, rather
+        than Arrow decimals. The text is formatted by the ODBC driver, so the digits are exactly the
+        ones reported by the data source. Default is ``False``.
+    :param bit_as_boolean: If ``True`` (default) boolean columns like SQL Server ``BIT`` are read as
+        Arrow booleans. ``False`` reads them as ``Int8`` instead, which allows pipelines depending
+        on an integer representation to keep working.
+    :param connect_deadline_sec: Maximum time in seconds to wait for the connection to the data
+        source to be established, before an ``Error`` is raised. In contrast to a login timeout this
+        does not depend on the driver honoring it. ``None`` waits indefinitely.
+    :param pair_columns_as_map: Zero based indices of a key and a value column, e.g. of an
+        entity-attribute-value shaped result set. The key column is replaced by a column of maps,
+        each holding a single entry made up of the key and the value of the row. The value column
+        is removed. Rows with a ``NULL`` key yield a ``NULL`` map. ``partition_by`` refers to the
+        columns after this transformation.
+    :param column_order: Zero based indices of the columns to emit, in the order they should appear
+        in the batches. Columns may be omitted or repeated. This is applied after
+        ``pair_columns_as_map``, and ``partition_by`` refers to the columns after reordering.
+    :param parse_pg_arrays: Zero based indices of text columns holding PostgreSQL array literals,
+        like ``{1,2,NULL}``. PostgreSQL drivers report array columns as text. These columns are
+        parsed into lists of strings instead. Pass a dictionary mapping the indices to an element
+        type, e.g. ``{0: pyarrow.int32()}`` for an ``integer[]`` column, to cast the elements to it.
+        Elements which can not be cast become ``None``. Only one dimensional arrays are supported,
+        nested arrays raise an ``Error``. Indices refer to the columns after ``column_order`` has
+        been applied.
+    :param fixed_size_lists: Maps zero based column indices to an element type and a list size,
+        e.g. ``{2: (pyarrow.float32(), 768)}``. These columns are read as fixed size lists, which
+        suits e.g. embeddings. Text is parsed as comma separated numbers, optionally enclosed in
+        brackets, like ``[0.5,1,2]``. Binary is read as consecutive little endian floats. Supported
+        element types are ``float32`` and ``float64``. Indices refer to the columns after
+        ``column_order`` has been applied.
+    :param schema_metadata: Key value pairs attached to the schema of the batches as metadata, e.g.
+        the source query or the time of the extraction. The metadata is part of the schema
+        exported to pyarrow and therefore survives e.g. writing the batches to parquet files.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :param column_names: Names of the columns in the emitted schema, in the order of the result
+        set. Entries which are ``None`` or empty keep the name reported by the data source, and so
+        do columns past the end of the list. The names are assigned before any other
+        transformation, so ``pair_columns_as_map`` uses the new name of the key column.
+    :param char_as_bool: Maps zero based column indices to a pair of ASCII characters representing
+        ``True`` and ``False``, e.g. ``{3: ("Y", "N")}``. These single character text columns, like
+        the flags of many legacy schemas, are read as Arrow booleans. Trailing spaces are ignored,
+        other values are read as ``None``. Indices refer to the columns after ``column_order`` has
+        been applied.
+    :param empty_strings_as_null: If ``True`` empty strings in text columns are read as ``None``.
+        Oracle does not distinguish between the two, so this allows reading the same logical data
+        consistently from Oracle and other data sources. Default is ``False``.
+    :param raw_binary_columns: Zero based indices of columns in the result set, which are fetched as
+        raw bytes into binary columns, independent of their SQL type. The bytes are the C
+        representation of the value the driver delivers for ``SQL_C_BINARY``, so they depend on
+        driver and platform (e.g. the byte order of integers). ``NULL`` is read as ``None``. The
+        buffers are sized after the column size reported by the driver, so values with a larger
+        binary representation, like those of small precision ``NUMERIC`` columns, are truncated.
+    :param max_batch_rows: Upper bound for the number of rows in each batch. Larger batches are
+        split into slices without copying the values. In contrast to ``batch_size`` this is a
+        hard cap, which also holds for the batches emitted after ``partition_by`` or the
+        transformations. ``None`` (default) does not split batches.
+    :param downcast_integers: If ``True`` ``int64`` columns of each batch are cast to the narrowest
+        integer type holding all of its values, which saves memory if e.g. a ``BIGINT`` column
+        holds only small values. This is decided for each batch individually, so the type of a
+        column may differ between batches and from ``BatchReader.schema``. Cast the batches back
+        before concatenating them. Only affects iterating over the reader. Default is ``False``.
+    :param on_progress: Invoked with the total number of rows fetched so far, each time another
+        ``progress_every_rows`` rows have been fetched from the data source. It is called from
+        within the fetch, no matter how the batches are consumed (e.g. also by ``to_parquet`` or a
+        stream converted from the reader with ``into_pyarrow_record_batch_reader``).
+    :param progress_every_rows: Number of rows between two invocations of ``on_progress``. As the
+        rows are counted per fetched batch, the callback is invoked at most once per batch.
+    :param mask_columns: Maps zero based column indices to a mask hiding their values, so e.g.
+        personally identifiable information never reaches Python. ``"sha256"`` replaces each value
+        with the hex encoded SHA-256 hash of its bytes, so equal values still yield equal hashes.
+        Values which are neither text nor binary are formatted as text first. ``"redact"`` replaces
+        all values with ``None``. Indices refer to the columns after ``column_order`` has been
+        applied.
+    :param large_lists: If ``True``, columns parsed with ``parse_pg_arrays`` are emitted as
+        ``large_list`` of ``large_string``. Their 64 Bit offsets do not overflow for batches holding
+        more than 2 GiB of elements or text. Fixed size lists and maps have no such choice.
+    :param retry_sql_states: SQLSTATEs of transient errors, e.g. ``["40001"]`` for serialization
+        failures, upon which connecting and executing the query is retried. Errors raised while
+        fetching batches are not retried, since batches may already have been consumed.
+    :param max_retries: Maximum number of retries for errors listed in ``retry_sql_states``.
+    :param retry_backoff_sec: Seconds to wait before the first retry. The wait doubles with each
+        further retry.
     :return: In case the query does not produce a result set (e.g. in case of an INSERT statement),
         ``None`` is returned. Should the statement return a result set a ``BatchReader`` is
         returned, which implements the iterator protocol and iterates over individual arrow batches.
+        The schema is inferred and the buffers are allocated and bound before this function
+        returns. Errors doing so are raised right here, rather than while fetching the first batch.
     """
+    check_parameters(parameters)
     query_bytes = query.encode("utf-8")
+    (query_tag_bytes, query_tag_len) = to_bytes_and_len(query_tag)
 
-    connection = connect_to_database(connection_string, user, password)
+    if max_text_size is None:
+        max_text_size = 0
+
+    if max_binary_size is None:
+        max_binary_size = 0
+
+    if raw_binary_columns is None:
+        raw_binary_array = ffi.NULL
+        raw_binary_len = 0
+    else:
+        raw_binary_array = ffi.new("uintptr_t[]", raw_binary_columns)
+        raw_binary_len = len(raw_binary_columns)
+
+    attempt = 0
+    while True:
+        try:
+            reader = _make_reader(
+                query_bytes,
+                query_tag_bytes,
+                query_tag_len,
+                batch_size,
+                parameters,
+                max_text_size,
+                max_binary_size,
+                falliable_allocations,
+                decimal_as_text,
+                bit_as_boolean,
+                raw_binary_array,
+                raw_binary_len,
+                connection_string,
+                user,
+                password,
+                connect_deadline_sec,
+                environment,
+            )
+            break
+        except Error as error:
+            if attempt >= max_retries or error.sql_state() not in (retry_sql_states or []):
+                raise
+            sleep(retry_backoff_sec * 2**attempt)
+            attempt += 1
+
+    if reader == ffi.NULL:
+        # The query ran successfully but did not produce a result set
+        return None
+
+    # Take ownership of the reader first, so it is freed, should configuring it fail.
+    batch_reader = BatchReader(reader)
+
+    if column_names is not None:
+        names = [name.encode("utf-8") if name else None for name in column_names]
+        # Rust copies the text, so the buffers only need to outlive this call.
+        name_bufs = ffi.new(
+            "uint8_t *[]", [ffi.NULL if name is None else ffi.from_buffer(name) for name in names]
+        )
+        error = lib.arrow_odbc_reader_rename_columns(
+            reader, name_bufs, [0 if name is None else len(name) for name in names], len(names)
+        )
+        raise_on_error(error)
+
+    if pair_columns_as_map is not None:
+        (key_index, value_index) = pair_columns_as_map
+        error = lib.arrow_odbc_reader_pair_columns_as_map(reader, key_index, value_index)
+        raise_on_error(error)
+
+    if column_order is not None:
+        order = ffi.new("uintptr_t[]", column_order)
+        error = lib.arrow_odbc_reader_reorder_columns(reader, order, len(column_order))
+        raise_on_error(error)
+
+    for column_index, mask in (mask_columns or {}).items():
+        if mask not in _MASK_MODES:
+            raise ValueError(f"Unknown mask {mask!r}. Supported are 'sha256' and 'redact'.")
+        error = lib.arrow_odbc_reader_mask_column(reader, column_index, _MASK_MODES[mask])
+        raise_on_error(error)
+
+    if isinstance(parse_pg_arrays, dict):
+        pg_arrays = list(parse_pg_arrays.items())
+    else:
+        pg_arrays = [(column_index, None) for column_index in parse_pg_arrays or []]
+    for column_index, element_type in pg_arrays:
+        with arrow_ffi.new("struct ArrowSchema*") as c_element_type:
+            if element_type is not None:
+                element_type._export_to_c(int(arrow_ffi.cast("uintptr_t", c_element_type)))
+            error = lib.arrow_odbc_reader_parse_pg_arrays(
+                reader,
+                column_index,
+                ffi.NULL if element_type is None else c_element_type,
+                large_lists,
+            )
+        raise_on_error(error)
+
+    for column_index, (element_type, size) in (fixed_size_lists or {}).items():
+        if element_type == float32():
+            element_type_code = 0
+        elif element_type == float64():
+            element_type_code = 1
+        else:
+            raise ValueError(f"Unsupported element type for fixed size lists: {element_type}")
+        error = lib.arrow_odbc_reader_column_as_fixed_list(
+            reader, column_index, element_type_code, size
+        )
+        raise_on_error(error)
+
+    for column_index, (true_char, false_char) in (char_as_bool or {}).items():
+        true_bytes = true_char.encode("ascii")
+        false_bytes = false_char.encode("ascii")
+        if len(true_bytes) != 1 or len(false_bytes) != 1:
+            raise ValueError("Boolean flags must be single ASCII characters.")
+        error = lib.arrow_odbc_reader_char_as_bool(
+            reader, column_index, true_bytes[0], false_bytes[0]
+        )
+        raise_on_error(error)
+
+    if empty_strings_as_null:
+        lib.arrow_odbc_reader_empty_strings_as_null(reader)
+
+    lib.arrow_odbc_reader_downcast_integers(reader, downcast_integers)
+
+    if schema_metadata is not None:
+        keys = [key.encode("utf-8") for key in schema_metadata.keys()]
+        values = [value.encode("utf-8") for value in schema_metadata.values()]
+        # Rust copies the text, so the buffers only need to outlive this call.
+        key_bufs = ffi.new("uint8_t *[]", [ffi.from_buffer(key) for key in keys])
+        value_bufs = ffi.new("uint8_t *[]", [ffi.from_buffer(value) for value in values])
+        error = lib.arrow_odbc_reader_set_schema_metadata(
+            reader,
+            key_bufs,
+            [len(key) for key in keys],
+            value_bufs,
+            [len(value) for value in values],
+            len(keys),
+        )
+        raise_on_error(error)
+
+    # Schema may have been altered by the transformations
+    batch_reader._update_schema()
+
+    if partition_by is not None:
+        error = lib.arrow_odbc_reader_partition_by(reader, partition_by)
+        raise_on_error(error)
+
+    if max_batch_rows is not None:
+        error = lib.arrow_odbc_reader_set_max_batch_rows(reader, max_batch_rows)
+        raise_on_error(error)
+
+    if on_progress is not None:
+        key = next(_progress_keys)
+        _progress_callbacks[key] = on_progress
+        error = lib.arrow_odbc_reader_set_progress_callback(
+            reader, progress_every_rows, _on_progress, _release_progress, key
+        )
+        if error != ffi.NULL:
+            # The reader did not take the callback, so it does not release it either
+            del _progress_callbacks[key]
+        raise_on_error(error)
+
+    return batch_reader
+
+
+def _make_reader(
+    query_bytes: bytes,
+    query_tag_bytes: Any,
+    query_tag_len: int,
+    batch_size: int,
+    parameters: Optional[List[Any]],
+    max_text_size: int,
+    max_binary_size: int,
+    falliable_allocations: bool,
+    decimal_as_text: bool,
+    bit_as_boolean: bool,
+    raw_binary_array: Any,
+    raw_binary_len: int,
+    connection_string: str,
+    user: Optional[str],
+    password: Optional[str],
+    connect_deadline_sec: Optional[float],
+    environment: Optional[Environment],
+) -> Any:
+    """
+    Connects to the data source and executes the query. Returns the handle of the
+    ``ArrowOdbcReader``, or ``NULL`` if the query did not produce a result set.
+    """
+    connection = connect_to_database(
+        connection_string, user, password, connect_deadline_sec, environment
+    )
 
     # Connecting to the database has been successful. Note that connection does not truly take
     # ownership of the connection. If it runs out of scope (e.g. due to a raised exception) the
@@ -133,26 +663,7 @@ def read_arrow_batches_from_odbc(
     # is infalliable. arrow_odbc_reader_make will truly take ownership of the connection. Even if it
     # should fail, it will be closed correctly.
 
-    if parameters is None:
-        parameters_array = FFI.NULL
-        parameters_len = 0
-        encoded_parameters = []
-    else:
-        parameters_array = ffi.new("ArrowOdbcParameter *[]", len(parameters))
-        parameters_len = len(parameters)
-        # Must be kept alive. Within Rust code we only allocate an additional
-        # indicator the string payload is just referenced.
-        encoded_parameters = [to_bytes_and_len(p) for p in parameters]
-
-    if max_text_size is None:
-        max_text_size = 0
-
-    if max_binary_size is None:
-        max_binary_size = 0
-
-    for p_index in range(0, parameters_len):
-        (p_bytes, p_len) = encoded_parameters[p_index]
-        parameters_array[p_index] = lib.arrow_odbc_parameter_string_make(p_bytes, p_len)
+    (parameters_array, parameters_len) = to_parameter_array(parameters)
 
     reader_out = ffi.new("ArrowOdbcReader **")
 
@@ -160,12 +671,18 @@ def read_arrow_batches_from_odbc(
         connection,
         query_bytes,
         len(query_bytes),
+        query_tag_bytes,
+        query_tag_len,
         batch_size,
         parameters_array,
         parameters_len,
         max_text_size,
         max_binary_size,
         falliable_allocations,
+        decimal_as_text,
+        bit_as_boolean,
+        raw_binary_array,
+        raw_binary_len,
         reader_out,
     )
 
@@ -173,9 +690,4 @@ def read_arrow_batches_from_odbc(
     # error if not
     raise_on_error(error)
 
-    reader = reader_out[0]
-    if reader == ffi.NULL:
-        # The query ran successfully but did not produce a result set
-        return None
-    else:
-        return BatchReader(reader)
+    return reader_out[0]
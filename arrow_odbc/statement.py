@@ -0,0 +1,70 @@
+from typing import Any, List, Optional
+
+from .connect import connect_to_database
+from ._native import ffi, lib  # type: ignore
+from .environment import Environment
+from .error import raise_on_error
+from .parameter import check_parameters, to_parameter_array
+
+
+def execute(
+    query: str,
+    connection_string: str,
+    user: Optional[str] = None,
+    password: Optional[str] = None,
+    parameters: Optional[List[Any]] = None,
+    connect_deadline_sec: Optional[float] = None,
+    environment: Optional[Environment] = None,
+) -> Optional[int]:
+    """
+    Execute a statement which does not produce a result set, like e.g. ``INSERT``, ``UPDATE`` or
+    ``CREATE INDEX``. Should the statement produce a result set nonetheless, it is discarded.
+
+    :param query: The SQL statement to execute.
+    :param connection_string: ODBC Connection string used to connect to the data source. To find a
+        connection string for your data source try https://www.connectionstrings.com/.
+    :param user: Allows for specifying the user seperatly from the connection string if it is not
+        already part of it. The value will eventually be escaped and attached to the connection
+        string as `UID`.
+    :param password: Allows for specifying the password seperatly from the connection string if it
+        is not already part of it. The value will eventually be escaped and attached to the
+        connection string as `PWD`.
+    :param parameters: ODBC allows you to use a question mark as placeholder marker (``?``) for
+        positional parameters. This argument takes a list of parameters those number must match the
+        number of placholders in the SQL statement. You can use `None` to pass `NULL`, or
+        ``DEFAULT`` to let a column fall back to its default value. Parameters of different kinds
+        may be mixed within the same list. A parameter of any other type raises a ``TypeError``.
+    :param connect_deadline_sec: Maximum time in seconds to wait for the connection to the data
+        source to be established, before an ``Error`` is raised. In contrast to a login timeout this
+        does not depend on the driver honoring it. ``None`` waits indefinitely.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :return: Number of rows affected by the statement. ``None`` if the driver does not report it.
+    """
+    check_parameters(parameters)
+    query_bytes = query.encode("utf-8")
+
+    connection = connect_to_database(
+        connection_string, user, password, connect_deadline_sec, environment
+    )
+
+    (parameters_array, parameters_len) = to_parameter_array(parameters)
+
+    rows_affected_out = ffi.new("int64_t *")
+    error = lib.arrow_odbc_connection_execute(
+        connection,
+        query_bytes,
+        len(query_bytes),
+        parameters_array,
+        parameters_len,
+        rows_affected_out,
+    )
+    # In contrast to the reader, executing does not take ownership of the connection.
+    lib.arrow_odbc_connection_free(connection)
+    raise_on_error(error)
+
+    rows_affected = rows_affected_out[0]
+    if rows_affected == -1:
+        return None
+    else:
+        return rows_affected
@@ -1,7 +1,9 @@
 from typing import Optional, Any
 
 from pyarrow.cffi import ffi as arrow_ffi
-from arrow_odbc.connect import connect_to_database
+from arrow_odbc.connect import connect_to_database, to_bytes_and_len
+from arrow_odbc.environment import Environment
+from arrow_odbc.error import raise_on_error
 
 from ._native import ffi, lib  # type: ignore
 
@@ -28,7 +30,8 @@ class BatchWriter:
         """
         Fills the internal buffers of the writer with data from the batch. Every
         time they are full, the data is send to the database. To make sure all
-        the data is is send ``flush`` must be called.
+        the data is is send ``flush`` must be called. The batch may contain more
+        rows than ``chunk_size``, in which case it is sent in several roundtrips.
         """
         with arrow_ffi.new("struct ArrowArray*") as c_array, \
             arrow_ffi.new("struct ArrowSchema*") as c_schema:
@@ -41,13 +44,15 @@ class BatchWriter:
             batch._export_to_c(c_array_ptr)
             batch.schema._export_to_c(c_schema_ptr)
 
-            lib.arrow_odbc_writer_write_batch(self.handle, c_array, c_schema)
+            error = lib.arrow_odbc_writer_write_batch(self.handle, c_array, c_schema)
+            raise_on_error(error)
 
     def flush(self):
         """
         Inserts the remaining rows of the last chunk to the database.
         """
-        lib.arrow_odbc_writer_flush(self.handle)
+        error = lib.arrow_odbc_writer_flush(self.handle)
+        raise_on_error(error)
 
 def insert_into_table(
     reader: Any,
@@ -56,6 +61,9 @@ def insert_into_table(
     connection_string: str,
     user: Optional[str] = None,
     password: Optional[str] = None,
+    connect_deadline_sec: Optional[float] = None,
+    environment: Optional[Environment] = None,
+    schema: Optional[str] = None,
 ):
     """
     Consume the batches in the reader and insert them into a table on the database.
@@ -64,9 +72,16 @@ def insert_into_table(
         attribute, referencing an Arrow schema. Each field in the schema must correspond to a
         column in the table with identical name.
     :param chunk_size: Number of records to insert in each roundtrip to the database. Independent of
-        batch size (i.e. number of rows in an individual record batch).
+        batch size (i.e. number of rows in an individual record batch). Rows are bound as arrays of
+        parameters, so each roundtrip executes the insert statement for up to ``chunk_size`` rows at
+        once. Larger values usually speed up large loads. Vendor specific bulk copy interfaces like
+        SQL Server ``bcp`` or PostgreSQL ``COPY`` are not part of the ODBC standard and therefore
+        not used.
     :param table: Name of a database table to insert into. Used to generate the insert statement for
-        the bulk writer.
+        the bulk writer. The writer only issues ``INSERT`` statements and makes no assumptions about
+        the table beyond its columns, so this may also be an updatable view, e.g. one with an
+        ``INSTEAD OF`` trigger. The name is used verbatim, so it may already be qualified or
+        quoted.
     :param connection_string: ODBC Connection string used to connect to the data source. To find a
         connection string for your data source try https://www.connectionstrings.com/.
     :param user: Allows for specifying the user seperatly from the connection string if it is not
@@ -75,8 +90,19 @@ def insert_into_table(
     :param password: Allows for specifying the password seperatly from the connection string if it
         is not already part of it. The value will eventually be escaped and attached to the
         connection string as `PWD`.
+    :param connect_deadline_sec: Maximum time in seconds to wait for the connection to the data
+        source to be established, before an ``Error`` is raised. In contrast to a login timeout this
+        does not depend on the driver honoring it. ``None`` waits indefinitely.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    :param schema: Name of the database schema the table belongs to, e.g. ``analytics``. Like the
+        table name it is used verbatim, so quote it yourself (e.g. ``'"Analytics"'``) to match it
+        exactly, including case. The quoting rules depend on the data source. ``None`` (default)
+        inserts into the table as resolved by the data source, usually within the default schema
+        of the user.
     """
     table_bytes = table.encode("utf-8")
+    (schema_bytes, schema_len) = to_bytes_and_len(schema)
 
     # Allocate structures where we will export the Array data and the Array schema. They will be
     # released when we exit the with block.
@@ -87,7 +113,9 @@ def insert_into_table(
         # Export the schema to the C Data structures.
         reader.schema._export_to_c(c_schema_ptr)
 
-        connection = connect_to_database(connection_string, user, password)
+        connection = connect_to_database(
+            connection_string, user, password, connect_deadline_sec, environment
+        )
 
         # Connecting to the database has been successful. Note that connection does not truly take
         # ownership of the connection. If it runs out of scope (e.g. due to a raised exception) the
@@ -96,9 +124,82 @@ def insert_into_table(
         # connection will be closed.
 
         writer_out = ffi.new("ArrowOdbcWriter **")
-        lib.arrow_odbc_writer_make(
-            connection, table_bytes, len(table_bytes), chunk_size, c_schema, writer_out
+        error = lib.arrow_odbc_writer_make(
+            connection,
+            table_bytes,
+            len(table_bytes),
+            schema_bytes,
+            schema_len,
+            chunk_size,
+            c_schema,
+            writer_out,
+        )
+        raise_on_error(error)
+        writer = BatchWriter(writer_out[0])
+
+    # Write all batches in reader
+    for batch in reader:
+        writer.write_batch(batch)
+    writer.flush()
+
+def execute_many(
+    reader: Any,
+    chunk_size: int,
+    query: str,
+    connection_string: str,
+    user: Optional[str] = None,
+    password: Optional[str] = None,
+    connect_deadline_sec: Optional[float] = None,
+    environment: Optional[Environment] = None,
+):
+    """
+    Consume the batches in the reader and execute a parameterized statement once for each row, e.g.
+    an ``UPDATE`` or an ``INSERT`` with a custom column list. Like ``insert_into_table`` the rows
+    are bound as arrays of parameters, so the statement is executed for many rows in one roundtrip.
+
+    :param reader: Reader is used to iterate over record batches. It must expose a `schema`
+        attribute, referencing an Arrow schema. The columns are bound positionally to the
+        placeholders of the query, so their number must match. Names of the fields are ignored.
+    :param chunk_size: Number of rows to send in each roundtrip to the database. Independent of
+        batch size (i.e. number of rows in an individual record batch).
+    :param query: SQL statement with one question mark (``?``) as placeholder for each column, e.g.
+        ``UPDATE Sales SET price = ? WHERE id = ?``.
+    :param connection_string: ODBC Connection string used to connect to the data source. To find a
+        connection string for your data source try https://www.connectionstrings.com/.
+    :param user: Allows for specifying the user seperatly from the connection string if it is not
+        already part of it. The value will eventually be escaped and attached to the connection
+        string as `UID`.
+    :param password: Allows for specifying the password seperatly from the connection string if it
+        is not already part of it. The value will eventually be escaped and attached to the
+        connection string as `PWD`.
+    :param connect_deadline_sec: Maximum time in seconds to wait for the connection to the data
+        source to be established, before an ``Error`` is raised. In contrast to a login timeout this
+        does not depend on the driver honoring it. ``None`` waits indefinitely.
+    :param environment: ODBC environment to open the connection with. ``None`` uses the environment
+        shared by the entire process.
+    """
+    query_bytes = query.encode("utf-8")
+
+    # Allocate structures where we will export the Array data and the Array schema. They will be
+    # released when we exit the with block.
+    with arrow_ffi.new("struct ArrowSchema*") as c_schema:
+        # Get the references to the C Data structures.
+        c_schema_ptr = int(arrow_ffi.cast("uintptr_t", c_schema))
+
+        # Export the schema to the C Data structures.
+        reader.schema._export_to_c(c_schema_ptr)
+
+        connection = connect_to_database(
+            connection_string, user, password, connect_deadline_sec, environment
+        )
+
+        # `arrow_odbc_writer_prepare` takes ownership of the connection. Even if it should fail the
+        # connection will be closed.
+        writer_out = ffi.new("ArrowOdbcWriter **")
+        error = lib.arrow_odbc_writer_prepare(
+            connection, query_bytes, len(query_bytes), chunk_size, c_schema, writer_out
         )
+        raise_on_error(error)
         writer = BatchWriter(writer_out[0])
 
     # Write all batches in reader
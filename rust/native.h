@@ -3,6 +3,12 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Opaque type owning an ODBC environment. Connections opened with it are isolated from the ones
+ * opened using the environment shared by default.
+ */
+typedef struct ArrowOdbcEnvironment ArrowOdbcEnvironment;
+
 /**
  * Handle to an error emmitted by arrow odbc
  */
@@ -13,6 +19,12 @@ typedef struct ArrowOdbcError ArrowOdbcError;
  */
 typedef struct ArrowOdbcParameter ArrowOdbcParameter;
 
+/**
+ * Opaque type holding a statement, which has been prepared once and can be executed repeatedly
+ * with different parameters. This type also has ownership of the ODBC Connection handle.
+ */
+typedef struct ArrowOdbcPreparedStatement ArrowOdbcPreparedStatement;
+
 /**
  * Opaque type holding all the state associated with an ODBC reader implementation in Rust. This
  * type also has ownership of the ODBC Connection handle.
@@ -36,18 +48,188 @@ typedef struct OdbcConnection OdbcConnection;
  *
  * # Safety
  *
+ * `environment` may be `NULL`, in which case the environment shared by default is used. If not,
+ * it must point to a valid `ArrowOdbcEnvironment`.
  * `connection_string_buf` must point to a valid utf-8 encoded string. `connection_string_len` must
  * hold the length of text in `connection_string_buf`.
  * `user` and or `password` are optional and are allowed to be `NULL`.
+ * `connect_deadline_ms` is the maximum time in milliseconds to wait for the connection to be
+ * established. `0` waits indefinitely. In contrast to a login timeout, the deadline does not rely
+ * on the driver. Connecting happens on a separate thread, which is abandoned once the deadline is
+ * exceeded. Should it connect eventually nonetheless, the connection is closed right away.
  */
-struct ArrowOdbcError *arrow_odbc_connect_with_connection_string(const uint8_t *connection_string_buf,
+struct ArrowOdbcError *arrow_odbc_connect_with_connection_string(const struct ArrowOdbcEnvironment *environment,
+                                                                 const uint8_t *connection_string_buf,
                                                                  uintptr_t connection_string_len,
                                                                  const uint8_t *user,
                                                                  uintptr_t user_len,
                                                                  const uint8_t *password,
                                                                  uintptr_t password_len,
+                                                                 uint64_t connect_deadline_ms,
                                                                  struct OdbcConnection **connection_out);
 
+/**
+ * Frees the resources associated with an OdbcConnection
+ *
+ * # Safety
+ *
+ * `connection` must point to a valid OdbcConnection.
+ */
+void arrow_odbc_connection_free(struct OdbcConnection *connection);
+
+/**
+ * Executes a statement which is not expected to produce a result set, e.g. an `INSERT`,
+ * `UPDATE` or `CREATE INDEX`. Should the statement produce a result set nonetheless, it is
+ * discarded. The connection is borrowed and can be used further afterwards.
+ *
+ * # Safety
+ *
+ * * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+ * * `query_buf` must point to a valid utf-8 string
+ * * `query_len` describes the len of `query_buf` in bytes.
+ * * `parameters` must contain only valid pointers. This function takes ownership of all of them
+ *   independent if the function succeeds or not. Yet it does not take ownership of the array
+ *   itself.
+ * * `parameters_len` number of elements in parameters.
+ * * `rows_affected_out` is assigned the number of rows affected by the statement, or `-1` if the
+ *   driver does not report it.
+ */
+struct ArrowOdbcError *arrow_odbc_connection_execute(struct OdbcConnection *connection,
+                                                     const uint8_t *query_buf,
+                                                     uintptr_t query_len,
+                                                     struct ArrowOdbcParameter *const *parameters,
+                                                     uintptr_t parameters_len,
+                                                     int64_t *rows_affected_out);
+
+/**
+ * Checks the syntax of a connection string and that the driver or data source it refers to is
+ * known to the driver manager. No connection to the data source is opened.
+ *
+ * # Safety
+ *
+ * `connection_string_buf` must point to a valid utf-8 encoded string. `connection_string_len` must
+ * hold the length of text in `connection_string_buf`.
+ */
+struct ArrowOdbcError *arrow_odbc_validate_connection_string(const uint8_t *connection_string_buf,
+                                                            uintptr_t connection_string_len);
+
+/**
+ * Assembles the driver specific connection string attributes selecting a transport, like named
+ * pipes or Unix domain sockets, to reach `target`. The attributes can be appended to a connection
+ * string.
+ *
+ * `transport` is one of:
+ *
+ * * `0`: SQL Server over TCP/IP. `target` is `host[,port]`.
+ * * `1`: SQL Server over named pipes. `target` is the pipe, e.g. `\\.\pipe\sql\query`.
+ * * `2`: SQL Server over shared memory. `target` is the local instance, e.g. `.`.
+ * * `3`: PostgreSQL over a Unix domain socket. `target` is the directory holding the socket.
+ * * `4`: MySQL or MariaDB over a Unix domain socket. `target` is the path of the socket.
+ *
+ * # Safety
+ *
+ * * `target_buf` must point to a valid utf-8 string. `target_len` must hold its length in bytes.
+ * * `out_buf` must point to a buffer of at least `out_capacity` bytes, which is filled with the
+ *   utf-8 encoded attributes, if they fit.
+ * * `out_len` is assigned the length of the attributes in bytes, even if they do not fit into
+ *   `out_buf`. In that case the call can be repeated with a larger buffer.
+ */
+struct ArrowOdbcError *arrow_odbc_transport_attributes(uint8_t transport,
+                                                       const uint8_t *target_buf,
+                                                       uintptr_t target_len,
+                                                       uint8_t *out_buf,
+                                                       uintptr_t out_capacity,
+                                                       uintptr_t *out_len);
+
+/**
+ * Builds a connection string from a template, replacing each `{key}` placeholder with the value
+ * for `key`. Values are escaped, so they can not inject further attributes. Braces which do not
+ * enclose one of the keys, like in `Driver={ODBC Driver 17 for SQL Server}`, are left untouched.
+ *
+ * # Safety
+ *
+ * * `template_buf` must point to a valid utf-8 string. `template_len` must hold its length in
+ *   bytes.
+ * * `keys` and `values` must each point to `count` pointers to valid utf-8 strings. They may be
+ *   `NULL` if `count` is `0`.
+ * * `key_lens` and `value_lens` must each point to `count` lengths in bytes of the respective
+ *   strings. They may be `NULL` if `count` is `0`.
+ * * `out_buf` must point to a buffer of at least `out_capacity` bytes, which is filled with the
+ *   utf-8 encoded connection string, if it fits.
+ * * `out_len` is assigned the length of the connection string in bytes, even if it does not fit
+ *   into `out_buf`. In that case the call can be repeated with a larger buffer.
+ */
+struct ArrowOdbcError *arrow_odbc_build_connection_string(const uint8_t *template_buf,
+                                                          uintptr_t template_len,
+                                                          const uint8_t *const *keys,
+                                                          const uintptr_t *key_lens,
+                                                          const uint8_t *const *values,
+                                                          const uintptr_t *value_lens,
+                                                          uintptr_t count,
+                                                          uint8_t *out_buf,
+                                                          uintptr_t out_capacity,
+                                                          uintptr_t *out_len);
+
+/**
+ * Queries the maximum length of a column name in the data source. Useful to validate or truncate
+ * identifiers before generating tables.
+ *
+ * # Safety
+ *
+ * * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+ * * `max_len_out` is assigned the maximum length of a column name in characters. `0` indicates
+ *   that there is no maximum length, or that it is unknown.
+ */
+struct ArrowOdbcError *arrow_odbc_connection_max_column_name_len(struct OdbcConnection *connection,
+                                                                uint16_t *max_len_out);
+
+/**
+ * Queries the default value definitions of the columns of a table, as reported in the `COLUMN_DEF`
+ * column of `SQLColumns`. The result is a batch with the columns `column_name` and `column_def`,
+ * the latter being `NULL` for columns without a default. Definitions are the text reported by the
+ * driver, e.g. `((42))` or `(getdate())` for SQL Server, and can be used to replicate tables.
+ *
+ * # Safety
+ *
+ * * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+ * * `catalog_buf` may be `NULL`, in which case the current catalog of the connection is used. If
+ *   not, it must point to a valid utf-8 string of `catalog_len` bytes.
+ * * `schema_name_buf` may be `NULL`, in which case tables in all schemas match. If not, it must
+ *   point to a valid utf-8 string of `schema_name_len` bytes.
+ * * `table_buf` must point to a valid utf-8 string of `table_len` bytes.
+ * * `array` and `schema` must point to valid FFI structures, which are filled with the batch.
+ */
+struct ArrowOdbcError *arrow_odbc_connection_column_defaults(struct OdbcConnection *connection,
+                                                            const uint8_t *catalog_buf,
+                                                            uintptr_t catalog_len,
+                                                            const uint8_t *schema_name_buf,
+                                                            uintptr_t schema_name_len,
+                                                            const uint8_t *table_buf,
+                                                            uintptr_t table_len,
+                                                            void *array,
+                                                            void *schema);
+
+/**
+ * Allocates an ODBC environment of its own.
+ *
+ * # Safety
+ *
+ * `environment_out` must point to a valid pointer. In case of success it is assigned an instance
+ * of `ArrowOdbcEnvironment`. Ownership is transferred to the caller.
+ */
+struct ArrowOdbcError *arrow_odbc_environment_make(struct ArrowOdbcEnvironment **environment_out);
+
+/**
+ * Frees the resources associated with an ArrowOdbcEnvironment
+ *
+ * # Safety
+ *
+ * `environment` must point to a valid ArrowOdbcEnvironment. Connections, readers and writers
+ * created with it share ownership of the environment, so it is only deallocated once the last of
+ * them has been freed.
+ */
+void arrow_odbc_environment_free(struct ArrowOdbcEnvironment *environment);
+
 /**
  * Deallocates the resources associated with an error.
  *
@@ -66,16 +248,225 @@ void arrow_odbc_error_free(struct ArrowOdbcError *error);
  */
 const char *arrow_odbc_error_message(const struct ArrowOdbcError *error);
 
+/**
+ * The SQLSTATE reported by the ODBC driver as zero terminated string, e.g. `23000`. `NULL` if the
+ * error did not originate from a diagnostic record of the driver.
+ *
+ * # Safety
+ *
+ * Error must be a valid non null pointer to an Error.
+ */
+const char *arrow_odbc_error_sql_state(const struct ArrowOdbcError *error);
+
+/**
+ * Classifies the error by the class of its SQLSTATE:
+ *
+ * * `0`: Other, including errors without SQLSTATE
+ * * `1`: Connection error (`08`)
+ * * `2`: Constraint violation (`23`)
+ * * `3`: Timeout (`HYT00`, `HYT01`)
+ * * `4`: Deadlock or serialization failure (`40`)
+ * * `5`: Syntax error or access violation (`42`)
+ * * `6`: Data error (`22`)
+ *
+ * # Safety
+ *
+ * Error must be a valid non null pointer to an Error.
+ */
+uint8_t arrow_odbc_error_category(const struct ArrowOdbcError *error);
+
 /**
  * # Safety
  *
  * `char_buf` may be `NULL`, but if it is not, it must contain a valid utf-8 sequence not shorter
- * than `char_len`. This function does not take ownership of the parameter. The parameter must at
- * least be valid until the call make reader is finished.
+ * than `char_len`. This function does not take ownership of `char_buf`, the text is copied into
+ * the parameter. A `NULL` pointer binds `NULL`, whereas a valid pointer with a `char_len` of `0`
+ * binds an empty string.
  */
 struct ArrowOdbcParameter *arrow_odbc_parameter_string_make(const uint8_t *char_buf,
                                                             uintptr_t char_len);
 
+/**
+ * A binary parameter, bound as `VARBINARY`. Binds `NULL` if `is_null` is `TRUE`, in which case
+ * the other arguments are ignored. Otherwise an empty buffer binds an empty value.
+ *
+ * # Safety
+ *
+ * Unless `is_null` is `TRUE`, `bytes_buf` must point to at least `len` bytes. This function does
+ * not take ownership of `bytes_buf`, the bytes are copied into the parameter.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_binary_make(const uint8_t *bytes_buf,
+                                                            uintptr_t len,
+                                                            bool is_null);
+
+/**
+ * A binary parameter, bound as `VARBINARY`, which borrows the bytes rather than copying them. This
+ * avoids holding large values, like files uploaded as BLOBs, twice in memory.
+ *
+ * # Safety
+ *
+ * `buf` must point to at least `len` bytes. The parameter borrows the bytes, so they must stay
+ * valid and unchanged until the statement the parameter is passed to has been executed. This
+ * usually means until the function taking ownership of the parameter returns. Unlike other
+ * parameters, `buf` must not be `NULL`.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_binary_borrowed_make(const uint8_t *buf,
+                                                                     uintptr_t len);
+
+/**
+ * A 64 bit integer parameter, bound as `BIGINT`. Binds `NULL` if `is_null` is `TRUE`, in which
+ * case `value` is ignored.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_i64_make(int64_t value, bool is_null);
+
+/**
+ * A boolean parameter, bound as `BIT`. Binds `NULL` if `is_null` is `TRUE`, in which case `value`
+ * is ignored.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_bool_make(bool value, bool is_null);
+
+/**
+ * A double precision floating point parameter, bound as `DOUBLE`. Binds `NULL` if `is_null` is
+ * `TRUE`, in which case `value` is ignored.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_f64_make(double value, bool is_null);
+
+/**
+ * A date parameter, bound as `DATE`. Dates are proleptic Gregorian, so dates before the
+ * introduction of the Gregorian calendar are passed as is. Binds `NULL` if `is_null` is `TRUE`,
+ * in which case the other arguments are ignored.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_date_make(int16_t year,
+                                                          uint8_t month,
+                                                          uint8_t day,
+                                                          bool is_null);
+
+/**
+ * A decimal parameter, bound as `NUMERIC` with the precision and scale of the value, so no digits
+ * are lost to floating point. The value is `mantissa * 10^-scale`, e.g. a mantissa of `-12345`
+ * with a scale of `2` is `-123.45`.
+ *
+ * # Safety
+ *
+ * `mantissa_buf` must point to at least `mantissa_len` bytes, holding the mantissa as decimal
+ * digits with an optional leading sign. The digits are copied into the parameter. `parameter_out`
+ * must point to a valid pointer. In case of success it is assigned an instance of
+ * `ArrowOdbcParameter`. Ownership is transferred to the caller.
+ */
+struct ArrowOdbcError *arrow_odbc_parameter_decimal_make(const uint8_t *mantissa_buf,
+                                                         uintptr_t mantissa_len,
+                                                         int16_t scale,
+                                                         struct ArrowOdbcParameter **parameter_out);
+
+/**
+ * A parameter binding `SQL_DEFAULT_PARAM` as indicator. This causes the column to fall back to
+ * its default value, e.g. in an `INSERT` statement. Not all drivers support this, in which case
+ * executing the statement fails.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_default_make(void);
+
+/**
+ * A `NULL` parameter of the given SQL type. Some drivers, e.g. for Oracle, require the type of a
+ * `NULL` to match the column it is compared to or inserted into. `sql_data_type` is the code of
+ * the type as defined by ODBC, e.g. `12` for `SQL_VARCHAR`, `4` for `SQL_INTEGER`, `-5` for
+ * `SQL_BIGINT`, `8` for `SQL_DOUBLE`, `91` for `SQL_TYPE_DATE` or `93` for `SQL_TYPE_TIMESTAMP`.
+ */
+struct ArrowOdbcParameter *arrow_odbc_parameter_null_make(int16_t sql_data_type);
+
+/**
+ * Reduce overhead of opening connections by reusing them. Pooling is driver aware, i.e. each
+ * driver manages its own pool of connections.
+ *
+ * # Safety
+ *
+ * Pooling is determined at the time the ODBC environment is allocated, which happens with the
+ * first connection opened in the process. Changing the pooling setting afterwards is undefined
+ * behaviour according to the ODBC standard, so this should be called before any connection is
+ * opened.
+ */
+struct ArrowOdbcError *arrow_odbc_enable_connection_pooling(void);
+
+/**
+ * Switches connection pooling off again. Off is the default.
+ *
+ * # Safety
+ *
+ * Same as for [`arrow_odbc_enable_connection_pooling`]. Changing the pooling setting after
+ * connections have been opened is undefined behaviour according to the ODBC standard.
+ */
+struct ArrowOdbcError *arrow_odbc_disable_connection_pooling(void);
+
+/**
+ * Controls how closely connection attributes must match, in order for a pooled connection to be
+ * reused. `strict` (the default) requires the connection strings to match exactly. Otherwise the
+ * matching is relaxed and up to the driver.
+ *
+ * Must be called before the first connection is opened, as the setting is applied to the ODBC
+ * environment once it is allocated.
+ */
+struct ArrowOdbcError *arrow_odbc_set_cp_match(bool strict);
+
+/**
+ * Prepares a statement, so the data source parses and plans it only once, rather than for each
+ * execution.
+ *
+ * Takes ownership of connection even in case of an error.
+ *
+ * # Safety
+ *
+ * * `connection` must point to a valid OdbcConnection. This function takes ownership of the
+ *   connection, even in case of an error. So The connection must not be freed explicitly
+ *   afterwards.
+ * * `query_buf` must point to a valid utf-8 string
+ * * `query_len` describes the len of `query_buf` in bytes.
+ * * `statement_out` in case of success this will point to an instance of
+ *   `ArrowOdbcPreparedStatement`. Ownership is transferred to the caller.
+ */
+struct ArrowOdbcError *arrow_odbc_connection_prepare(struct OdbcConnection *connection,
+                                                     const uint8_t *query_buf,
+                                                     uintptr_t query_len,
+                                                     struct ArrowOdbcPreparedStatement **statement_out);
+
+/**
+ * Frees the resources associated with an ArrowOdbcPreparedStatement
+ *
+ * # Safety
+ *
+ * `statement` must point to a valid ArrowOdbcPreparedStatement. A reader created by the last
+ * execution of the statement may still be alive. It keeps the statement open until it is freed.
+ */
+void arrow_odbc_prepared_statement_free(struct ArrowOdbcPreparedStatement *statement);
+
+/**
+ * Executes the prepared statement with the parameters passed. The statement may be executed
+ * again, once the reader of the previous execution has been freed. Executing it while that reader
+ * is still alive returns an error. The remaining arguments are the ones of
+ * [`crate::arrow_odbc_reader_make`].
+ *
+ * # Safety
+ *
+ * * `statement` must point to a valid ArrowOdbcPreparedStatement. Ownership is not transferred.
+ * * `parameters` must contain only valid pointers. This function takes ownership of all of them
+ *   independent if the function succeeds or not. Yet it does not take ownership of the array
+ *   itself. Their number must match the number of placeholders in the statement.
+ * * `reader_out` in case of success this will point to an instance of `ArrowOdbcReader`, or
+ *   `NULL` if the statement does not produce a result set. Ownership is transferred to the
+ *   caller. The reader owns the statement until it is freed, even if it is converted into a
+ *   stream.
+ */
+struct ArrowOdbcError *arrow_odbc_prepared_execute(struct ArrowOdbcPreparedStatement *statement,
+                                                   struct ArrowOdbcParameter *const *parameters,
+                                                   uintptr_t parameters_len,
+                                                   uintptr_t batch_size,
+                                                   uintptr_t max_text_size,
+                                                   uintptr_t max_binary_size,
+                                                   bool fallibale_allocations,
+                                                   bool decimal_as_text,
+                                                   bool bit_as_boolean,
+                                                   const uintptr_t *raw_binary_columns,
+                                                   uintptr_t raw_binary_columns_len,
+                                                   struct ArrowOdbcReader **reader_out);
+
 /**
  * Creates an Arrow ODBC reader instance.
  *
@@ -89,6 +480,10 @@ struct ArrowOdbcParameter *arrow_odbc_parameter_string_make(const uint8_t *char_
  *   afterwards.
  * * `query_buf` must point to a valid utf-8 string
  * * `query_len` describes the len of `query_buf` in bytes.
+ * * `query_tag_buf` may be `NULL`. If not, it must point to a valid utf-8 string, which is
+ *   prepended to the query as an SQL comment. This helps identifying the query in the
+ *   monitoring of the database.
+ * * `query_tag_len` describes the len of `query_tag_buf` in bytes.
  * * `parameters` must contain only valid pointers. This function takes ownership of all of them
  *   independent if the function succeeds or not. Yet it does not take ownership of the array
  *   itself.
@@ -100,18 +495,33 @@ struct ArrowOdbcParameter *arrow_odbc_parameter_string_make(const uint8_t *char_
  * * `fallibale_allocations`: `TRUE` if allocations should return an error, `FALSE` if it is fine
  *   to abort the process. Enabling might have a performance overhead, so it might be desirable to
  *   disable it, if you know there is enough memory available.
+ * * `decimal_as_text`: `TRUE` if decimal columns should be read as text (`Utf8`), rather than
+ *   Arrow decimals.
+ * * `bit_as_boolean`: `TRUE` if boolean columns (e.g. `BIT`) should be read as Arrow booleans.
+ *   `FALSE` reads them as `Int8` instead.
+ * * `raw_binary_columns` may be `NULL`. If not, it must point to `raw_binary_columns_len` zero
+ *   based column indices. These columns are fetched as `SQL_C_BINARY` into Arrow `Binary`,
+ *   independent of their SQL type. `NULL` values are read as `NULL`. The buffers are sized after
+ *   the column size reported by the driver, so values with a larger binary representation (e.g.
+ *   of small precision `NUMERIC` columns) are truncated.
  * * `reader_out` in case of success this will point to an instance of `ArrowOdbcReader`.
  *   Ownership is transferred to the caller.
  */
 struct ArrowOdbcError *arrow_odbc_reader_make(struct OdbcConnection *connection,
                                               const uint8_t *query_buf,
                                               uintptr_t query_len,
+                                              const uint8_t *query_tag_buf,
+                                              uintptr_t query_tag_len,
                                               uintptr_t batch_size,
                                               struct ArrowOdbcParameter *const *parameters,
                                               uintptr_t parameters_len,
                                               uintptr_t max_text_size,
                                               uintptr_t max_binary_size,
                                               bool fallibale_allocations,
+                                              bool decimal_as_text,
+                                              bool bit_as_boolean,
+                                              const uintptr_t *raw_binary_columns,
+                                              uintptr_t raw_binary_columns_len,
                                               struct ArrowOdbcReader **reader_out);
 
 /**
@@ -128,17 +538,317 @@ void arrow_odbc_reader_free(struct ArrowOdbcReader *reader);
  *
  * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
  * * `array_out` and `schema_out` must both point to valid pointers, which themselves may be null.
+ * * `has_more_out` may be `NULL`. If not, and a batch has been handed out, it is assigned `1` if
+ *   further batches follow, or `0` if the batch is the last one. To find out, the next batch is
+ *   fetched ahead of time, so this delays handing out the current one. An error fetching ahead is
+ *   reported by the next call, rather than this one.
  */
 struct ArrowOdbcError *arrow_odbc_reader_next(struct ArrowOdbcReader *reader,
                                               void *array,
                                               void *schema,
-                                              int *has_next_out);
+                                              int *has_next_out,
+                                              int *has_more_out);
 
 /**
  * Retrieve the associated schema from a reader.
  */
 struct ArrowOdbcError *arrow_odbc_reader_schema(struct ArrowOdbcReader *reader, void *out_schema);
 
+/**
+ * Consumes the reader and exports it as an Arrow C stream.
+ *
+ * The stream always carries the schema inferred from the result set. An empty result set
+ * therefore yields a valid stream with zero batches, rather than no stream at all.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`]. This function
+ *   takes ownership of the reader. So the reader must not be freed explicitly afterwards.
+ * * `stream_out` must point to a valid `FFI_ArrowArrayStream`. The caller is responsible for
+ *   releasing the stream.
+ */
+void arrow_odbc_reader_into_stream(struct ArrowOdbcReader *reader, void *stream_out);
+
+/**
+ * Fetches all remaining batches of the reader and spills them to Arrow IPC files. Each batch is
+ * written into its own file within `dir`, named after the zero based index of the batch (e.g.
+ * `0000000000.arrow`). This way the batches never need to be held in memory at the same time.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `dir_buf` must point to a valid utf-8 string, naming an existing directory.
+ * * `dir_len` describes the len of `dir_buf` in bytes.
+ * * `num_batches_out` is assigned the number of files written.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_spill_to_dir(struct ArrowOdbcReader *reader,
+                                                      const uint8_t *dir_buf,
+                                                      uintptr_t dir_len,
+                                                      uintptr_t *num_batches_out);
+
+/**
+ * Fetches all remaining batches of the reader and writes them into a single parquet file. Batches
+ * are written as they are fetched, so the result set never needs to be held in memory at once.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `path_buf` must point to a valid utf-8 string, naming the file to create. An existing file is
+ *   overwritten.
+ * * `path_len` describes the len of `path_buf` in bytes.
+ * * `row_group_size` maximum number of rows in each row group of the parquet file. Must be at
+ *   least 1.
+ * * `num_rows_out` is assigned the number of rows written.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_to_parquet(struct ArrowOdbcReader *reader,
+                                                    const uint8_t *path_buf,
+                                                    uintptr_t path_len,
+                                                    uintptr_t row_group_size,
+                                                    uintptr_t *num_rows_out);
+
+/**
+ * Fetches all remaining batches of the reader and writes them into a CSV file. Batches are written
+ * as they are fetched, so the result set never needs to be held in memory at once.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `path_buf` must point to a valid utf-8 string, naming the file to create. An existing file is
+ *   overwritten.
+ * * `path_len` describes the len of `path_buf` in bytes.
+ * * `delimiter` separates the fields, e.g. `b','`.
+ * * `header`: `TRUE` if the column names should be written as first line. No header is written
+ *   for a result set without batches.
+ * * `num_rows_out` is assigned the number of rows written.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_to_csv(struct ArrowOdbcReader *reader,
+                                                const uint8_t *path_buf,
+                                                uintptr_t path_len,
+                                                uint8_t delimiter,
+                                                bool header,
+                                                uintptr_t *num_rows_out);
+
+/**
+ * Fetches all remaining batches without exporting them over the C boundary. `on_batch` is invoked
+ * with the number of rows of each batch, e.g. to report progress.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `on_batch` must be a valid function pointer.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_drain_with_callback(struct ArrowOdbcReader *reader,
+                                                             void (*on_batch)(uintptr_t));
+
+/**
+ * Invokes `callback` with `context` and the total number of rows fetched from the data source so
+ * far, each time another `every_rows` rows have been fetched. The callback is invoked on the
+ * thread fetching the batches, from within the call fetching them, independent of how the batches
+ * are consumed. Once the reader, or a stream it has been converted into, is freed `release` is
+ * invoked with `context`, so the caller knows the callback is no longer needed.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `callback` must be a valid function pointer, until `release` has been invoked.
+ * * `release` must be a valid function pointer, for as long as the reader is alive. It is not
+ *   invoked, if this function returns an error.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_set_progress_callback(struct ArrowOdbcReader *reader,
+                                                               uintptr_t every_rows,
+                                                               void (*callback)(uintptr_t, uintptr_t),
+                                                               void (*release)(uintptr_t),
+                                                               uintptr_t context);
+
+/**
+ * Fetches all remaining batches and exports them as an Arrow C stream. In contrast to
+ * [`arrow_odbc_reader_into_stream`] all batches are fetched before this function returns, and the
+ * stream merely hands out batches already held in memory. The reader itself is not consumed.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `stream_out` must point to a valid `FFI_ArrowArrayStream`. The caller is responsible for
+ *   releasing the stream.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_read_all(struct ArrowOdbcReader *reader, void *stream_out);
+
+/**
+ * Splits each batch fetched from the data source into one batch per distinct value of the column
+ * at `column_index`. Rows within each partition keep their original order. Partitioning happens
+ * within a fetched batch only, so the same value may appear in several emitted batches, if it
+ * spans the boundary of a fetch.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_partition_by(struct ArrowOdbcReader *reader,
+                                                      uintptr_t column_index);
+
+/**
+ * Guarantees that no batch handed out holds more than `max_rows` rows. Larger batches are split
+ * into slices sharing the buffers of the fetched batch, so no values are copied. In contrast to
+ * the batch size passed to [`arrow_odbc_reader_make`] this also bounds the batches emitted by
+ * [`arrow_odbc_reader_partition_by`] or transformations.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_set_max_batch_rows(struct ArrowOdbcReader *reader,
+                                                            uintptr_t max_rows);
+
+/**
+ * Replaces the key column with a column of maps, each holding a single entry made up of the key
+ * and value of the row. The value column is removed. Rows with a `NULL` key yield a `NULL` map.
+ * Column indices are zero based and refer to the schema before this transformation.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_pair_columns_as_map(struct ArrowOdbcReader *reader,
+                                                            uintptr_t key_index,
+                                                            uintptr_t value_index);
+
+/**
+ * Emits the columns at the zero based indices in `order`, in the order specified. Columns may be
+ * omitted or repeated.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `order` must point to an array of `order_len` column indices.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_reorder_columns(struct ArrowOdbcReader *reader,
+                                                        const uintptr_t *order,
+                                                        uintptr_t order_len);
+
+/**
+ * Parses the text in the column at the zero based `column_index` as PostgreSQL array literals
+ * (e.g. `{1,2,NULL}`) and emits lists instead. The elements are cast from text to `element_type`,
+ * or stay strings if it is `NULL`. Elements which can not be cast become `NULL`. Only one
+ * dimensional arrays are supported, nested arrays are reported as an error. If `large` is `TRUE`
+ * the column is emitted as `LargeList`, whose 64 Bit offsets can not overflow for big batches.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `element_type` may be `NULL`. If not, it must point to an Arrow schema describing the type
+ *   of the elements. Ownership is not transferred.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_parse_pg_arrays(struct ArrowOdbcReader *reader,
+                                                        uintptr_t column_index,
+                                                        const void *element_type,
+                                                        bool large);
+
+/**
+ * Assigns names to the columns of the batches, rather than using the ones reported by the data
+ * source. Entries which are `NULL` or empty keep the name reported by the data source.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `names` must point to `count` pointers, each either `NULL` or pointing to a valid utf-8
+ *   string. It may be `NULL` if `count` is `0`.
+ * * `name_lens` must point to `count` lengths in bytes of the respective names. It may be `NULL`
+ *   if `count` is `0`.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_rename_columns(struct ArrowOdbcReader *reader,
+                                                        const uint8_t *const *names,
+                                                        const uintptr_t *name_lens,
+                                                        uintptr_t count);
+
+/**
+ * Reads the single character text column at the zero based `column_index` as booleans, e.g. the
+ * `'Y'` and `'N'` flags of legacy schemas. Values other than `true_char` or `false_char` are read
+ * as `NULL`. Trailing spaces are ignored.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_char_as_bool(struct ArrowOdbcReader *reader,
+                                                      uintptr_t column_index,
+                                                      uint8_t true_char,
+                                                      uint8_t false_char);
+
+/**
+ * If `enabled`, `Int64` columns of each batch handed out by [`arrow_odbc_reader_next`] are cast to
+ * the narrowest signed integer type able to hold all values of the batch. This happens per batch,
+ * so the type of a column may differ between batches and from the schema of the reader. Batches
+ * exported as stream, or written into files, are not affected.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+void arrow_odbc_reader_downcast_integers(struct ArrowOdbcReader *reader, bool enabled);
+
+/**
+ * Reads empty strings in text columns as `NULL`. Data sources like Oracle do not distinguish
+ * between the two, so this normalizes the data read from other sources to match.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+void arrow_odbc_reader_empty_strings_as_null(struct ArrowOdbcReader *reader);
+
+/**
+ * Hides the values of the column at the zero based `column_index` before the batches are handed
+ * out, so e.g. personally identifiable information never leaves the native code.
+ *
+ * `mode` is one of:
+ *
+ * * `0`: Replaces each value with the hex encoded SHA-256 hash of its bytes. Values which are
+ *   neither text nor binary are formatted as text before hashing.
+ * * `1`: Replaces all values with `NULL`.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_mask_column(struct ArrowOdbcReader *reader,
+                                                     uintptr_t column_index,
+                                                     uint8_t mode);
+
+/**
+ * Attaches key value pairs as metadata to the schema of the batches, e.g. to record the
+ * provenance of the data. Existing entries with the same keys are replaced.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ * * `keys` and `values` must each point to `count` pointers to valid utf-8 strings. They may be
+ *   `NULL` if `count` is `0`.
+ * * `key_lens` and `value_lens` must each point to `count` lengths in bytes of the respective
+ *   strings. They may be `NULL` if `count` is `0`.
+ */
+struct ArrowOdbcError *arrow_odbc_reader_set_schema_metadata(struct ArrowOdbcReader *reader,
+                                                            const uint8_t *const *keys,
+                                                            const uintptr_t *key_lens,
+                                                            const uint8_t *const *values,
+                                                            const uintptr_t *value_lens,
+                                                            uintptr_t count);
+
+/**
+ * Reinterprets the column at the zero based `column_index` as fixed size lists of `size` floating
+ * point numbers, e.g. embeddings. Text is parsed as comma separated numbers, optionally enclosed
+ * in brackets (e.g. `[0.5,1,2]`). Binary is read as `size` consecutive little endian floats.
+ *
+ * `element_type` is `0` for `Float32` and `1` for `Float64` elements.
+ *
+ * # Safety
+ *
+ * * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+ */
+struct ArrowOdbcError *arrow_odbc_reader_column_as_fixed_list(struct ArrowOdbcReader *reader,
+                                                             uintptr_t column_index,
+                                                             uint8_t element_type,
+                                                             uintptr_t size);
+
 /**
  * Frees the resources associated with an ArrowOdbcWriter
  *
@@ -160,6 +870,10 @@ void arrow_odbc_writer_free(struct ArrowOdbcWriter *writer);
  *   afterwards.
  * * `table_buf` must point to a valid utf-8 string
  * * `table_len` describes the len of `table_buf` in bytes.
+ * * `schema_name_buf` may be `NULL` in which case the table is not qualified with a schema.
+ *   Otherwise it must point to a valid utf-8 string and the table is qualified with it. Schema and
+ *   table name are used verbatim in the insert statement, so they may already be quoted.
+ * * `schema_name_len` describes the len of `schema_name_buf` in bytes.
  * * `schema` pointer to an arrow schema.
  * * `writer_out` in case of success this will point to an instance of `ArrowOdbcWriter`. Ownership
  *   is transferred to the caller.
@@ -167,10 +881,38 @@ void arrow_odbc_writer_free(struct ArrowOdbcWriter *writer);
 struct ArrowOdbcError *arrow_odbc_writer_make(struct OdbcConnection *connection,
                                               const uint8_t *table_buf,
                                               uintptr_t table_len,
+                                              const uint8_t *schema_name_buf,
+                                              uintptr_t schema_name_len,
                                               uintptr_t chunk_size,
                                               const void *schema,
                                               struct ArrowOdbcWriter **writer_out);
 
+/**
+ * Creates an Arrow ODBC writer instance executing an arbitrary parameterized statement, rather
+ * than a generated `INSERT`. The columns of each written batch are bound positionally to the
+ * placeholders (`?`) of `sql_buf`. The statement is executed once for every row, using arrays of
+ * parameters to send up to `chunk_size` rows in one roundtrip.
+ *
+ * Takes ownership of connection even in case of an error.
+ *
+ * # Safety
+ *
+ * * `connection` must point to a valid OdbcConnection. This function takes ownership of the
+ *   connection, even in case of an error. So The connection must not be freed explicitly
+ *   afterwards.
+ * * `sql_buf` must point to a valid utf-8 string
+ * * `sql_len` describes the len of `sql_buf` in bytes.
+ * * `schema` pointer to an arrow schema.
+ * * `writer_out` in case of success this will point to an instance of `ArrowOdbcWriter`. Ownership
+ *   is transferred to the caller.
+ */
+struct ArrowOdbcError *arrow_odbc_writer_prepare(struct OdbcConnection *connection,
+                                                 const uint8_t *sql_buf,
+                                                 uintptr_t sql_len,
+                                                 uintptr_t chunk_size,
+                                                 const void *schema,
+                                                 struct ArrowOdbcWriter **writer_out);
+
 /**
  * # Safety
  *
@@ -187,3 +929,4 @@ struct ArrowOdbcError *arrow_odbc_writer_write_batch(struct ArrowOdbcWriter *wri
  * * `writer` must be valid non-null writer, allocated by [`arrow_odbc_writer_make`].
  */
 struct ArrowOdbcError *arrow_odbc_writer_flush(struct ArrowOdbcWriter *writer);
+
@@ -0,0 +1,259 @@
+//! Checks and assembles connection strings without opening a connection to the data source.
+
+use std::path::Path;
+
+use arrow_odbc::odbc_api::{escape_attribute_value, Environment};
+
+/// Parses the connection string and checks that the driver or data source it refers to is known to
+/// the driver manager. Returns a description of the first problem found.
+pub fn validate(env: &Environment, connection_string: &str) -> Result<(), String> {
+    let attributes = parse(connection_string)?;
+    let value_of = |name: &str| {
+        attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+
+    if let Some(driver) = value_of("DRIVER") {
+        // Drivers may also be referenced by the path to their shared library.
+        let is_path = driver.contains('/') || driver.contains('\\');
+        let installed = if is_path {
+            Path::new(driver).exists()
+        } else {
+            env.drivers()
+                .map_err(|e| e.to_string())?
+                .iter()
+                .any(|info| info.description.eq_ignore_ascii_case(driver))
+        };
+        if !installed {
+            return Err(format!("Driver '{}' is not installed.", driver));
+        }
+    } else if let Some(dsn) = value_of("DSN") {
+        let known = env
+            .data_sources()
+            .map_err(|e| e.to_string())?
+            .iter()
+            .any(|info| info.server_name.eq_ignore_ascii_case(dsn));
+        if !known {
+            return Err(format!("Data source '{}' is not configured.", dsn));
+        }
+    } else {
+        return Err("Connection string specifies neither DRIVER nor DSN.".to_string());
+    }
+    Ok(())
+}
+
+/// Transport used to reach the data source, each tied to the driver understanding it.
+pub enum Transport {
+    /// SQL Server over TCP/IP. Target is `host[,port]`.
+    SqlServerTcp,
+    /// SQL Server over named pipes. Target is the pipe, e.g. `\\.\pipe\sql\query`.
+    SqlServerNamedPipe,
+    /// SQL Server over shared memory. Target is the local instance, e.g. `.` or `.\SQLEXPRESS`.
+    SqlServerSharedMemory,
+    /// PostgreSQL over a Unix domain socket. Target is the directory holding the socket.
+    PostgresUnixSocket,
+    /// MySQL or MariaDB over a Unix domain socket. Target is the path of the socket.
+    MySqlUnixSocket,
+}
+
+impl Transport {
+    /// Transport for the code used over the C boundary. `None` if the code is unknown.
+    pub fn from_code(code: u8) -> Option<Self> {
+        let transport = match code {
+            0 => Transport::SqlServerTcp,
+            1 => Transport::SqlServerNamedPipe,
+            2 => Transport::SqlServerSharedMemory,
+            3 => Transport::PostgresUnixSocket,
+            4 => Transport::MySqlUnixSocket,
+            _ => return None,
+        };
+        Some(transport)
+    }
+}
+
+/// Connection string attributes selecting `transport` to reach `target`. The value is escaped, so
+/// the attributes can be appended to any connection string.
+pub fn transport_attributes(transport: Transport, target: &str) -> String {
+    let (name, value) = match transport {
+        Transport::SqlServerTcp => ("Server", format!("tcp:{target}")),
+        Transport::SqlServerNamedPipe => ("Server", format!("np:{target}")),
+        Transport::SqlServerSharedMemory => ("Server", format!("lpc:{target}")),
+        Transport::PostgresUnixSocket => ("Servername", target.to_owned()),
+        Transport::MySqlUnixSocket => ("Socket", target.to_owned()),
+    };
+    format!("{}={};", name, escape_attribute_value(&value))
+}
+
+/// Replaces each `{key}` placeholder in `template` with the escaped value of `key`. Braces not
+/// enclosing one of the keys, like in `Driver={ODBC Driver 17 for SQL Server}`, are left untouched.
+/// Values are escaped as a whole, so placeholders should make up the entire value of an attribute,
+/// e.g. `PWD={password};`.
+pub fn fill_template(template: &str, values: &[(String, String)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let candidate = &rest[start + 1..];
+        let value = candidate.find('}').and_then(|end| {
+            let key = &candidate[..end];
+            values
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| (value, end))
+        });
+        if let Some((value, end)) = value {
+            filled.push_str(&escape_attribute_value(value));
+            rest = &candidate[end + 1..];
+        } else {
+            filled.push('{');
+            rest = candidate;
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Splits a connection string into its `key=value` pairs. Values may be enclosed in braces, in
+/// which case they may contain `;` and `}}` stands for a literal `}`.
+fn parse(connection_string: &str) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    let mut rest = connection_string.trim_start();
+    while !rest.is_empty() {
+        let (key, after_key) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("Attribute '{}' is missing '='.", rest.trim_end()))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err("Attribute without a name.".to_string());
+        }
+        let (value, after_value) = if let Some(braced) = after_key.strip_prefix('{') {
+            let (value, after_value) = braced_value(braced).ok_or_else(|| {
+                format!("Value of attribute '{}' is missing a closing '}}'.", key)
+            })?;
+            (value.replace("}}", "}"), after_value)
+        } else {
+            let (value, after_value) = after_key.split_once(';').unwrap_or((after_key, ""));
+            (value.trim().to_string(), after_value)
+        };
+        attributes.push((key.to_string(), value));
+        rest = after_value.trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+    }
+    Ok(attributes)
+}
+
+/// Splits `text` after the opening brace into the braced value and the remainder after the closing
+/// brace. `None` if there is no closing brace.
+fn braced_value(text: &str) -> Option<(&str, &str)> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if c == '}' {
+            if let Some((_, '}')) = chars.peek() {
+                // Escaped closing brace.
+                chars.next();
+            } else {
+                return Some((&text[..index], &text[index + 1..]));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{braced_value, fill_template, parse};
+
+    fn pairs(attributes: &[(&str, &str)]) -> Vec<(String, String)> {
+        attributes
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_plain_attributes() {
+        let actual = parse("Driver=foo; Server = localhost ;").unwrap();
+
+        assert_eq!(pairs(&[("Driver", "foo"), ("Server", "localhost")]), actual);
+    }
+
+    #[test]
+    fn parse_braced_value_containing_semicolon() {
+        let actual = parse("PWD={a;b};UID=me").unwrap();
+
+        assert_eq!(pairs(&[("PWD", "a;b"), ("UID", "me")]), actual);
+    }
+
+    #[test]
+    fn parse_escaped_closing_brace() {
+        let actual = parse("PWD={a}}b};").unwrap();
+
+        assert_eq!(pairs(&[("PWD", "a}b")]), actual);
+    }
+
+    #[test]
+    fn parse_empty_value() {
+        let actual = parse("PWD=;UID=me").unwrap();
+
+        assert_eq!(pairs(&[("PWD", ""), ("UID", "me")]), actual);
+    }
+
+    #[test]
+    fn parse_attribute_without_name() {
+        assert!(parse("=foo;").is_err());
+    }
+
+    #[test]
+    fn parse_attribute_without_value() {
+        assert!(parse("Driver").is_err());
+    }
+
+    #[test]
+    fn parse_unterminated_brace() {
+        assert!(parse("PWD={abc").is_err());
+    }
+
+    #[test]
+    fn braced_value_stops_at_unescaped_brace() {
+        assert_eq!(Some(("a}}b", ";rest")), braced_value("a}}b};rest"));
+    }
+
+    #[test]
+    fn braced_value_without_closing_brace() {
+        assert_eq!(None, braced_value("a}}b"));
+    }
+
+    #[test]
+    fn fill_template_leaves_other_braces_untouched() {
+        let template = "Driver={ODBC Driver 17 for SQL Server};Server={host};";
+
+        let actual = fill_template(template, &pairs(&[("host", "localhost")]));
+
+        assert_eq!(
+            "Driver={ODBC Driver 17 for SQL Server};Server=localhost;",
+            actual
+        );
+    }
+
+    #[test]
+    fn fill_template_escapes_values() {
+        let actual = fill_template("PWD={password};", &pairs(&[("password", "a;b")]));
+
+        assert_eq!("PWD={a;b};", actual);
+    }
+
+    #[test]
+    fn fill_template_with_missing_key() {
+        let actual = fill_template("Server={host};Port={port};", &pairs(&[("host", "db")]));
+
+        assert_eq!("Server=db;Port={port};", actual);
+    }
+
+    #[test]
+    fn fill_template_with_unterminated_brace() {
+        let actual = fill_template("Server={host", &pairs(&[("host", "db")]));
+
+        assert_eq!("Server={host", actual);
+    }
+}
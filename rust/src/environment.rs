@@ -0,0 +1,63 @@
+use std::{
+    ptr::{null_mut, NonNull},
+    sync::Arc,
+};
+
+use arrow_odbc::odbc_api::{self, Environment};
+
+use crate::{pool::allocate_environment, try_, ArrowOdbcError, OdbcConnection, ENV};
+
+/// Opaque type owning an ODBC environment. Connections opened with it are isolated from the ones
+/// opened using the environment shared by default.
+pub struct ArrowOdbcEnvironment(Arc<Environment>);
+
+/// Allocates an ODBC environment of its own.
+///
+/// # Safety
+///
+/// `environment_out` must point to a valid pointer. In case of success it is assigned an instance
+/// of `ArrowOdbcEnvironment`. Ownership is transferred to the caller.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_environment_make(
+    environment_out: *mut *mut ArrowOdbcEnvironment,
+) -> *mut ArrowOdbcError {
+    let environment = try_!(allocate_environment());
+    *environment_out = Box::into_raw(Box::new(ArrowOdbcEnvironment(Arc::new(environment))));
+    null_mut()
+}
+
+/// Frees the resources associated with an ArrowOdbcEnvironment
+///
+/// # Safety
+///
+/// `environment` must point to a valid ArrowOdbcEnvironment. Connections, readers and writers
+/// created with it share ownership of the environment, so it is only deallocated once the last of
+/// them has been freed.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_environment_free(environment: NonNull<ArrowOdbcEnvironment>) {
+    drop(Box::from_raw(environment.as_ptr()));
+}
+
+/// The environment pointed to, or the shared default environment if `environment` is `NULL`.
+///
+/// # Safety
+///
+/// `environment` may be `NULL`. If not, it must point to a valid ArrowOdbcEnvironment.
+pub unsafe fn environment_or_default(environment: *const ArrowOdbcEnvironment) -> Arc<Environment> {
+    if environment.is_null() {
+        ENV.clone()
+    } else {
+        (*environment).0.clone()
+    }
+}
+
+/// Opens a connection, which shares ownership of `environment`.
+pub fn connect(
+    environment: Arc<Environment>,
+    connection_string: &str,
+) -> Result<OdbcConnection, odbc_api::Error> {
+    // Safe, since the connection holds on to the environment and is dropped before it.
+    let borrowed: &'static Environment = unsafe { &*Arc::as_ptr(&environment) };
+    let connection = borrowed.connect_with_connection_string(connection_string)?;
+    Ok(OdbcConnection(connection, environment))
+}
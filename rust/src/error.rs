@@ -1,16 +1,30 @@
-use std::{ffi::CString, fmt::Display, os::raw::c_char, ptr::NonNull};
+use std::{
+    any::Any,
+    error::Error,
+    ffi::CString,
+    fmt::Display,
+    os::raw::c_char,
+    ptr::{null, NonNull},
+};
+
+use arrow_odbc::{arrow::error::ArrowError, odbc_api, WriterError};
 
 /// Handle to an error emmitted by arrow odbc
 pub struct ArrowOdbcError {
     message: CString,
+    /// Five character SQLSTATE reported by the ODBC driver, if the error originates from it.
+    sql_state: Option<CString>,
 }
 
 impl ArrowOdbcError {
-    pub fn new(source: impl Display) -> ArrowOdbcError {
+    pub fn new(source: impl Display + 'static) -> ArrowOdbcError {
         let bytes = source.to_string();
         // Terminating Nul will be appended by `new`.
         let message = CString::new(bytes).unwrap();
-        ArrowOdbcError { message }
+        let sql_state = as_error(&source)
+            .and_then(sql_state)
+            .map(|state| CString::new(state.to_vec()).unwrap());
+        ArrowOdbcError { message, sql_state }
     }
 
     /// Moves the instance to the heap and return a pointer to it.
@@ -40,6 +54,81 @@ pub unsafe extern "C" fn arrow_odbc_error_message(error: *const ArrowOdbcError)
     error.message.as_ptr()
 }
 
+/// The SQLSTATE reported by the ODBC driver as zero terminated string, e.g. `23000`. `NULL` if the
+/// error did not originate from a diagnostic record of the driver.
+///
+/// # Safety
+///
+/// Error must be a valid non null pointer to an Error.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_error_sql_state(error: *const ArrowOdbcError) -> *const c_char {
+    let error = &*error;
+    error
+        .sql_state
+        .as_ref()
+        .map(|state| state.as_ptr())
+        .unwrap_or(null())
+}
+
+/// Classifies the error by the class of its SQLSTATE:
+///
+/// * `0`: Other, including errors without SQLSTATE
+/// * `1`: Connection error (`08`)
+/// * `2`: Constraint violation (`23`)
+/// * `3`: Timeout (`HYT00`, `HYT01`)
+/// * `4`: Deadlock or serialization failure (`40`)
+/// * `5`: Syntax error or access violation (`42`)
+/// * `6`: Data error (`22`)
+///
+/// # Safety
+///
+/// Error must be a valid non null pointer to an Error.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_error_category(error: *const ArrowOdbcError) -> u8 {
+    let error = &*error;
+    let state = match &error.sql_state {
+        Some(state) => state.as_bytes(),
+        None => return 0,
+    };
+    match state {
+        [b'0', b'8', ..] => 1,
+        [b'2', b'3', ..] => 2,
+        b"HYT00" | b"HYT01" => 3,
+        [b'4', b'0', ..] => 4,
+        [b'4', b'2', ..] => 5,
+        [b'2', b'2', ..] => 6,
+        _ => 0,
+    }
+}
+
+/// Views the errors, which may originate from ODBC, as `std::error::Error`. Other sources, like
+/// plain messages, yield `None`.
+fn as_error(source: &dyn Any) -> Option<&(dyn Error + 'static)> {
+    if let Some(error) = source.downcast_ref::<odbc_api::Error>() {
+        Some(error)
+    } else if let Some(error) = source.downcast_ref::<arrow_odbc::Error>() {
+        Some(error)
+    } else if let Some(error) = source.downcast_ref::<WriterError>() {
+        Some(error)
+    } else if let Some(ArrowError::ExternalError(error)) = source.downcast_ref::<ArrowError>() {
+        Some(error.as_ref())
+    } else {
+        None
+    }
+}
+
+/// Walks the chain of sources, looking for a diagnostic record of the ODBC driver.
+fn sql_state(error: &(dyn Error + 'static)) -> Option<[u8; 5]> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(odbc_api::Error::Diagnostics { record, .. }) = error.downcast_ref() {
+            return Some(record.state.0);
+        }
+        current = error.source();
+    }
+    None
+}
+
 #[macro_export]
 macro_rules! try_ {
     ($call:expr) => {
@@ -1,16 +1,45 @@
 //! Defines C bindings for `arrow-odbc` to enable using it from Python.
 
+mod connection_string;
+mod environment;
 mod error;
 mod parameter;
+mod partition;
+mod pool;
+mod prepared;
 mod reader;
+mod schema;
+mod transform;
 mod writer;
 
-use std::{borrow::Cow, ptr::null_mut, slice, str};
+use std::{
+    borrow::Cow,
+    ffi::c_void,
+    ptr::{null_mut, NonNull},
+    slice, str,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
 
-use arrow_odbc::odbc_api::{escape_attribute_value, Connection, Environment};
+use arrow_odbc::{
+    arrow::{
+        array::{Array, ArrayRef, StringArray},
+        compute::cast,
+        datatypes::DataType,
+        ffi::{FFI_ArrowArray, FFI_ArrowSchema},
+        record_batch::RecordBatch,
+    },
+    odbc_api::{self, escape_attribute_value, Connection, Environment},
+    BufferAllocationOptions, OdbcReader,
+};
 use lazy_static::lazy_static;
 
+use connection_string::{fill_template, transport_attributes, Transport};
+use environment::{connect, environment_or_default, ArrowOdbcEnvironment};
 pub use error::{arrow_odbc_error_free, arrow_odbc_error_message, ArrowOdbcError};
+use parameter::{parameters_from_raw, ArrowOdbcParameter};
+use reader::export_batch;
 pub use reader::{
     arrow_odbc_reader_free, arrow_odbc_reader_make, arrow_odbc_reader_next, ArrowOdbcReader,
 };
@@ -19,28 +48,40 @@ pub use writer::{
 };
 
 lazy_static! {
-    static ref ENV: Environment = Environment::new().unwrap();
+    static ref ENV: Arc<Environment> = Arc::new(pool::allocate_shared_environment().unwrap());
 }
 
-/// Opaque type to transport connection to an ODBC Datasource over language boundry
-pub struct OdbcConnection(Connection<'static>);
+/// Opaque type to transport connection to an ODBC Datasource over language boundry. Shares
+/// ownership of the environment the connection has been opened with, which is dropped after the
+/// connection.
+// SAFETY: The connection borrows the environment for `'static`, see `environment::connect`. Fields
+// are dropped in declaration order, so the environment must remain the last field.
+pub struct OdbcConnection(Connection<'static>, Arc<Environment>);
 
 /// Allocate and open an ODBC connection using the specified connection string. In case of an error
 /// this function returns a NULL pointer.
 ///
 /// # Safety
 ///
+/// `environment` may be `NULL`, in which case the environment shared by default is used. If not,
+/// it must point to a valid `ArrowOdbcEnvironment`.
 /// `connection_string_buf` must point to a valid utf-8 encoded string. `connection_string_len` must
 /// hold the length of text in `connection_string_buf`.
 /// `user` and or `password` are optional and are allowed to be `NULL`.
+/// `connect_deadline_ms` is the maximum time in milliseconds to wait for the connection to be
+/// established. `0` waits indefinitely. In contrast to a login timeout, the deadline does not rely
+/// on the driver. Connecting happens on a separate thread, which is abandoned once the deadline is
+/// exceeded. Should it connect eventually nonetheless, the connection is closed right away.
 #[no_mangle]
 pub unsafe extern "C" fn arrow_odbc_connect_with_connection_string(
+    environment: *const ArrowOdbcEnvironment,
     connection_string_buf: *const u8,
     connection_string_len: usize,
     user: *const u8,
     user_len: usize,
     password: *const u8,
     password_len: usize,
+    connect_deadline_ms: u64,
     connection_out: *mut *mut OdbcConnection,
 ) -> *mut ArrowOdbcError {
     let connection_string = slice::from_raw_parts(connection_string_buf, connection_string_len);
@@ -49,12 +90,339 @@ pub unsafe extern "C" fn arrow_odbc_connect_with_connection_string(
     append_attribute("UID", &mut connection_string, user, user_len);
     append_attribute("PWD", &mut connection_string, password, password_len);
 
-    let connection = try_!(ENV.connect_with_connection_string(&connection_string));
+    let environment = environment_or_default(environment);
+    let connection = if connect_deadline_ms == 0 {
+        try_!(connect(environment, &connection_string))
+    } else {
+        let deadline = Duration::from_millis(connect_deadline_ms);
+        match connect_with_deadline(environment, connection_string.into_owned(), deadline) {
+            Some(result) => try_!(result),
+            None => {
+                let message = format!(
+                    "Connecting to the data source did not finish within {}ms.",
+                    connect_deadline_ms
+                );
+                return ArrowOdbcError::new(message).into_raw();
+            }
+        }
+    };
+
+    *connection_out = Box::into_raw(Box::new(connection));
+    null_mut()
+}
+
+/// Connects on a separate thread. `None` if it did not finish within `deadline`. The thread owns a
+/// share of `environment`, so an abandoned attempt keeps it alive until it finishes.
+fn connect_with_deadline(
+    environment: Arc<Environment>,
+    connection_string: String,
+    deadline: Duration,
+) -> Option<Result<OdbcConnection, odbc_api::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // Sending fails if the deadline has been exceeded. The connection is dropped then.
+        let _ = sender.send(connect(environment, &connection_string));
+    });
+    receiver.recv_timeout(deadline).ok()
+}
+
+/// Frees the resources associated with an OdbcConnection
+///
+/// # Safety
+///
+/// `connection` must point to a valid OdbcConnection.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_connection_free(connection: NonNull<OdbcConnection>) {
+    drop(Box::from_raw(connection.as_ptr()));
+}
+
+/// Executes a statement which is not expected to produce a result set, e.g. an `INSERT`,
+/// `UPDATE` or `CREATE INDEX`. Should the statement produce a result set nonetheless, it is
+/// discarded. The connection is borrowed and can be used further afterwards.
+///
+/// # Safety
+///
+/// * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+/// * `query_buf` must point to a valid utf-8 string
+/// * `query_len` describes the len of `query_buf` in bytes.
+/// * `parameters` must contain only valid pointers. This function takes ownership of all of them
+///   independent if the function succeeds or not. Yet it does not take ownership of the array
+///   itself.
+/// * `parameters_len` number of elements in parameters.
+/// * `rows_affected_out` is assigned the number of rows affected by the statement, or `-1` if the
+///   driver does not report it.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_connection_execute(
+    connection: NonNull<OdbcConnection>,
+    query_buf: *const u8,
+    query_len: usize,
+    parameters: *const *mut ArrowOdbcParameter,
+    parameters_len: usize,
+    rows_affected_out: *mut i64,
+) -> *mut ArrowOdbcError {
+    let query = slice::from_raw_parts(query_buf, query_len);
+    let query = str::from_utf8(query).unwrap();
+
+    let parameters = parameters_from_raw(parameters, parameters_len);
+
+    let connection = &connection.as_ref().0;
+    let mut statement = try_!(connection.preallocate());
+    try_!(statement.execute(query, &parameters[..]));
+    let rows_affected = try_!(statement.row_count());
 
-    *connection_out = Box::into_raw(Box::new(OdbcConnection(connection)));
+    *rows_affected_out = rows_affected.map(|n| n as i64).unwrap_or(-1);
     null_mut()
 }
 
+/// Checks the syntax of a connection string and that the driver or data source it refers to is
+/// known to the driver manager. No connection to the data source is opened.
+///
+/// # Safety
+///
+/// `connection_string_buf` must point to a valid utf-8 encoded string. `connection_string_len` must
+/// hold the length of text in `connection_string_buf`.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_validate_connection_string(
+    connection_string_buf: *const u8,
+    connection_string_len: usize,
+) -> *mut ArrowOdbcError {
+    let connection_string = slice::from_raw_parts(connection_string_buf, connection_string_len);
+    let connection_string = str::from_utf8(connection_string).unwrap();
+    try_!(connection_string::validate(&ENV, connection_string));
+    null_mut()
+}
+
+/// Assembles the driver specific connection string attributes selecting a transport, like named
+/// pipes or Unix domain sockets, to reach `target`. The attributes can be appended to a connection
+/// string.
+///
+/// `transport` is one of:
+///
+/// * `0`: SQL Server over TCP/IP. `target` is `host[,port]`.
+/// * `1`: SQL Server over named pipes. `target` is the pipe, e.g. `\\.\pipe\sql\query`.
+/// * `2`: SQL Server over shared memory. `target` is the local instance, e.g. `.`.
+/// * `3`: PostgreSQL over a Unix domain socket. `target` is the directory holding the socket.
+/// * `4`: MySQL or MariaDB over a Unix domain socket. `target` is the path of the socket.
+///
+/// # Safety
+///
+/// * `target_buf` must point to a valid utf-8 string. `target_len` must hold its length in bytes.
+/// * `out_buf` must point to a buffer of at least `out_capacity` bytes, which is filled with the
+///   utf-8 encoded attributes, if they fit.
+/// * `out_len` is assigned the length of the attributes in bytes, even if they do not fit into
+///   `out_buf`. In that case the call can be repeated with a larger buffer.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_transport_attributes(
+    transport: u8,
+    target_buf: *const u8,
+    target_len: usize,
+    out_buf: *mut u8,
+    out_capacity: usize,
+    out_len: *mut usize,
+) -> *mut ArrowOdbcError {
+    let transport = match Transport::from_code(transport) {
+        Some(transport) => transport,
+        None => return ArrowOdbcError::new(format!("Unknown transport {transport}.")).into_raw(),
+    };
+    let target = slice::from_raw_parts(target_buf, target_len);
+    let target = str::from_utf8(target).unwrap();
+    let attributes = transport_attributes(transport, target);
+    write_text(&attributes, out_buf, out_capacity, out_len);
+    null_mut()
+}
+
+/// Builds a connection string from a template, replacing each `{key}` placeholder with the value
+/// for `key`. Values are escaped, so they can not inject further attributes. Braces which do not
+/// enclose one of the keys, like in `Driver={ODBC Driver 17 for SQL Server}`, are left untouched.
+///
+/// # Safety
+///
+/// * `template_buf` must point to a valid utf-8 string. `template_len` must hold its length in
+///   bytes.
+/// * `keys` and `values` must each point to `count` pointers to valid utf-8 strings. They may be
+///   `NULL` if `count` is `0`.
+/// * `key_lens` and `value_lens` must each point to `count` lengths in bytes of the respective
+///   strings. They may be `NULL` if `count` is `0`.
+/// * `out_buf` must point to a buffer of at least `out_capacity` bytes, which is filled with the
+///   utf-8 encoded connection string, if it fits.
+/// * `out_len` is assigned the length of the connection string in bytes, even if it does not fit
+///   into `out_buf`. In that case the call can be repeated with a larger buffer.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_build_connection_string(
+    template_buf: *const u8,
+    template_len: usize,
+    keys: *const *const u8,
+    key_lens: *const usize,
+    values: *const *const u8,
+    value_lens: *const usize,
+    count: usize,
+    out_buf: *mut u8,
+    out_capacity: usize,
+    out_len: *mut usize,
+) -> *mut ArrowOdbcError {
+    let template = slice::from_raw_parts(template_buf, template_len);
+    let template = str::from_utf8(template).unwrap();
+    let keys = try_!(strings_from_raw(keys, key_lens, count));
+    let values = try_!(strings_from_raw(values, value_lens, count));
+    let values: Vec<_> = keys
+        .into_iter()
+        .zip(values)
+        .map(|(key, value)| {
+            (
+                key.unwrap_or_default().to_owned(),
+                value.unwrap_or_default().to_owned(),
+            )
+        })
+        .collect();
+
+    let connection_string = fill_template(template, &values);
+    write_text(&connection_string, out_buf, out_capacity, out_len);
+    null_mut()
+}
+
+/// Queries the maximum length of a column name in the data source. Useful to validate or truncate
+/// identifiers before generating tables.
+///
+/// # Safety
+///
+/// * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+/// * `max_len_out` is assigned the maximum length of a column name in characters. `0` indicates
+///   that there is no maximum length, or that it is unknown.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_connection_max_column_name_len(
+    connection: NonNull<OdbcConnection>,
+    max_len_out: *mut u16,
+) -> *mut ArrowOdbcError {
+    let connection = &connection.as_ref().0;
+    *max_len_out = try_!(connection.max_column_name_len());
+    null_mut()
+}
+
+/// Zero based index of `COLUMN_NAME` in the result set of `SQLColumns`.
+const COLUMN_NAME: usize = 3;
+/// Zero based index of `COLUMN_DEF` in the result set of `SQLColumns`.
+const COLUMN_DEF: usize = 12;
+/// Upper bound for the length of a default value definition. Some drivers report the column as
+/// unbounded text.
+const MAX_COLUMN_DEF_LEN: usize = 4096;
+
+/// Queries the default value definitions of the columns of a table, as reported in the `COLUMN_DEF`
+/// column of `SQLColumns`. The result is a batch with the columns `column_name` and `column_def`,
+/// the latter being `NULL` for columns without a default. Definitions are the text reported by the
+/// driver, e.g. `((42))` or `(getdate())` for SQL Server, and can be used to replicate tables.
+///
+/// # Safety
+///
+/// * `connection` must point to a valid OdbcConnection. Ownership is not transferred.
+/// * `catalog_buf` may be `NULL`, in which case the current catalog of the connection is used. If
+///   not, it must point to a valid utf-8 string of `catalog_len` bytes.
+/// * `schema_name_buf` may be `NULL`, in which case tables in all schemas match. If not, it must
+///   point to a valid utf-8 string of `schema_name_len` bytes.
+/// * `table_buf` must point to a valid utf-8 string of `table_len` bytes.
+/// * `array` and `schema` must point to valid FFI structures, which are filled with the batch.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_connection_column_defaults(
+    connection: NonNull<OdbcConnection>,
+    catalog_buf: *const u8,
+    catalog_len: usize,
+    schema_name_buf: *const u8,
+    schema_name_len: usize,
+    table_buf: *const u8,
+    table_len: usize,
+    array: *mut c_void,
+    schema: *mut c_void,
+) -> *mut ArrowOdbcError {
+    let connection = &connection.as_ref().0;
+    // An empty catalog, schema or column name would only match objects without one, so these need
+    // defaults.
+    let catalog = if catalog_buf.is_null() {
+        try_!(connection.current_catalog())
+    } else {
+        str::from_utf8(slice::from_raw_parts(catalog_buf, catalog_len))
+            .unwrap()
+            .to_owned()
+    };
+    let schema_name = if schema_name_buf.is_null() {
+        "%"
+    } else {
+        str::from_utf8(slice::from_raw_parts(schema_name_buf, schema_name_len)).unwrap()
+    };
+    let table = str::from_utf8(slice::from_raw_parts(table_buf, table_len)).unwrap();
+
+    let cursor = try_!(connection.columns(&catalog, schema_name, table, "%"));
+    let buffer_allocation_options = BufferAllocationOptions {
+        max_text_size: Some(MAX_COLUMN_DEF_LEN),
+        max_binary_size: None,
+        fallibale_allocations: false,
+    };
+    let reader = try_!(OdbcReader::with(
+        cursor,
+        100,
+        None,
+        buffer_allocation_options
+    ));
+
+    let mut names = Vec::new();
+    let mut defaults = Vec::new();
+    for batch in reader {
+        let batch = try_!(batch);
+        let name = try_!(cast(batch.column(COLUMN_NAME), &DataType::Utf8));
+        let name = name.as_any().downcast_ref::<StringArray>().unwrap();
+        names.extend(name.iter().map(|name| name.unwrap_or_default().to_owned()));
+        let default = try_!(cast(batch.column(COLUMN_DEF), &DataType::Utf8));
+        let default = default.as_any().downcast_ref::<StringArray>().unwrap();
+        defaults.extend(default.iter().map(|default| default.map(str::to_owned)));
+    }
+
+    let batch = try_!(RecordBatch::try_from_iter([
+        ("column_name", Arc::new(StringArray::from(names)) as ArrayRef),
+        ("column_def", Arc::new(StringArray::from(defaults)) as ArrayRef),
+    ]));
+    try_!(export_batch(
+        batch,
+        array as *mut FFI_ArrowArray,
+        schema as *mut FFI_ArrowSchema
+    ));
+    null_mut()
+}
+
+/// Copies `text` into `buf`, if it fits within `capacity` bytes. `len_out` is assigned the length
+/// of `text` in bytes either way, so the caller is able to retry with a larger buffer.
+unsafe fn write_text(text: &str, buf: *mut u8, capacity: usize, len_out: *mut usize) {
+    if text.len() <= capacity {
+        slice::from_raw_parts_mut(buf, text.len()).copy_from_slice(text.as_bytes());
+    }
+    *len_out = text.len();
+}
+
+/// Strings passed as `count` pointers along with their lengths in bytes. `NULL` entries are `None`.
+/// Errors if a string is not valid utf-8.
+///
+/// # Safety
+///
+/// `bufs` and `lens` may be `NULL` if `count` is `0`. Otherwise they must each point to `count`
+/// elements. Each pointer in `bufs` must either be `NULL` or point to as many bytes as its length.
+unsafe fn strings_from_raw<'a>(
+    bufs: *const *const u8,
+    lens: *const usize,
+    count: usize,
+) -> Result<Vec<Option<&'a str>>, str::Utf8Error> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    slice::from_raw_parts(bufs, count)
+        .iter()
+        .zip(slice::from_raw_parts(lens, count))
+        .map(|(&buf, &len)| {
+            if buf.is_null() {
+                Ok(None)
+            } else {
+                str::from_utf8(slice::from_raw_parts(buf, len)).map(Some)
+            }
+        })
+        .collect()
+}
+
 /// Append attribute like user and value to connection string
 unsafe fn append_attribute(
     attribute_name: &'static str,
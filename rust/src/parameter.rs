@@ -1,43 +1,565 @@
-use std::slice;
+use std::{
+    ffi::c_void,
+    ptr::{null, null_mut},
+    slice, str,
+};
 
-use arrow_odbc::odbc_api::parameter::VarCharSlice;
+use arrow_odbc::odbc_api::{
+    handles::{CData, HasDataType},
+    parameter::{InputParameter, VarBinaryBox, VarBinarySlice, VarCharBox},
+    sys::{CDataType, Date, SqlDataType, NULL_DATA},
+    Bit, DataType, Nullable,
+};
+
+use crate::{try_, ArrowOdbcError};
 
 /// Opaque type holding a parameter intended to be bound to a placeholder (`?`) in an SQL query.
-pub struct ArrowOdbcParameter<'a>(VarCharSlice<'a>);
+pub struct ArrowOdbcParameter(Box<dyn InputParameter>);
 
-impl<'a> ArrowOdbcParameter<'a> {
-    fn from_opt_str(value: Option<&'a [u8]>) -> Self {
-        let inner = if let Some(slice) = value {
-            VarCharSlice::new(slice)
-        } else {
-            VarCharSlice::NULL
-        };
-        Self(inner)
+impl ArrowOdbcParameter {
+    fn new(parameter: impl InputParameter + 'static) -> Self {
+        Self(Box::new(parameter))
+    }
+
+    /// Moves the instance to the heap and return a pointer to it.
+    fn into_raw(self) -> *mut ArrowOdbcParameter {
+        Box::into_raw(Box::new(self))
     }
-}
 
-impl<'a> ArrowOdbcParameter<'a> {
-    pub fn unwrap(self) -> VarCharSlice<'a> {
+    pub fn unwrap(self) -> Box<dyn InputParameter> {
         self.0
     }
 }
 
+/// Takes ownership of the parameters passed over the C boundary.
+///
 /// # Safety
-/// 
+///
+/// `parameters` may be `NULL`. If it is not, it must point to an array of `parameters_len` valid
+/// pointers to `ArrowOdbcParameter`s. Ownership of the parameters is taken, yet not of the array
+/// itself.
+pub unsafe fn parameters_from_raw(
+    parameters: *const *mut ArrowOdbcParameter,
+    parameters_len: usize,
+) -> Vec<Box<dyn InputParameter>> {
+    if parameters.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(parameters, parameters_len)
+            .iter()
+            .map(|&p| Box::from_raw(p).unwrap())
+            .collect()
+    }
+}
+
+/// # Safety
+///
 /// `char_buf` may be `NULL`, but if it is not, it must contain a valid utf-8 sequence not shorter
-/// than `char_len`. This function does not take ownership of the parameter. The parameter must at
-/// least be valid until the call make reader is finished.
+/// than `char_len`. This function does not take ownership of `char_buf`, the text is copied into
+/// the parameter. A `NULL` pointer binds `NULL`, whereas a valid pointer with a `char_len` of `0`
+/// binds an empty string.
 #[no_mangle]
 pub unsafe extern "C" fn arrow_odbc_parameter_string_make(
     char_buf: *const u8,
     char_len: usize,
-) -> *mut ArrowOdbcParameter<'static> {
-    let opt = if char_buf.is_null() {
-        None
+) -> *mut ArrowOdbcParameter {
+    let parameter = if char_buf.is_null() {
+        VarCharBox::null()
+    } else {
+        VarCharBox::from_vec(slice::from_raw_parts(char_buf, char_len).to_owned())
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A binary parameter, bound as `VARBINARY`. Binds `NULL` if `is_null` is `TRUE`, in which case
+/// the other arguments are ignored. Otherwise an empty buffer binds an empty value.
+///
+/// # Safety
+///
+/// Unless `is_null` is `TRUE`, `bytes_buf` must point to at least `len` bytes. This function does
+/// not take ownership of `bytes_buf`, the bytes are copied into the parameter.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_parameter_binary_make(
+    bytes_buf: *const u8,
+    len: usize,
+    is_null: bool,
+) -> *mut ArrowOdbcParameter {
+    let parameter = if is_null {
+        VarBinaryBox::null()
+    } else {
+        VarBinaryBox::from_vec(slice::from_raw_parts(bytes_buf, len).to_owned())
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A binary parameter, bound as `VARBINARY`, which borrows the bytes rather than copying them. This
+/// avoids holding large values, like files uploaded as BLOBs, twice in memory.
+///
+/// # Safety
+///
+/// `buf` must point to at least `len` bytes. The parameter borrows the bytes, so they must stay
+/// valid and unchanged until the statement the parameter is passed to has been executed. This
+/// usually means until the function taking ownership of the parameter returns. Unlike other
+/// parameters, `buf` must not be `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_parameter_binary_borrowed_make(
+    buf: *const u8,
+    len: usize,
+) -> *mut ArrowOdbcParameter {
+    // The lifetime is not tracked by the compiler, but guaranteed by the caller.
+    let bytes: &'static [u8] = slice::from_raw_parts(buf, len);
+    ArrowOdbcParameter::new(VarBinarySlice::new(bytes)).into_raw()
+}
+
+/// A 64 bit integer parameter, bound as `BIGINT`. Binds `NULL` if `is_null` is `TRUE`, in which
+/// case `value` is ignored.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_i64_make(
+    value: i64,
+    is_null: bool,
+) -> *mut ArrowOdbcParameter {
+    let parameter = if is_null {
+        Nullable::null()
+    } else {
+        Nullable::new(value)
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A boolean parameter, bound as `BIT`. Binds `NULL` if `is_null` is `TRUE`, in which case `value`
+/// is ignored.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_bool_make(
+    value: bool,
+    is_null: bool,
+) -> *mut ArrowOdbcParameter {
+    let parameter = if is_null {
+        Nullable::null()
+    } else {
+        Nullable::new(Bit::from_bool(value))
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A double precision floating point parameter, bound as `DOUBLE`. Binds `NULL` if `is_null` is
+/// `TRUE`, in which case `value` is ignored.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_f64_make(
+    value: f64,
+    is_null: bool,
+) -> *mut ArrowOdbcParameter {
+    let parameter = if is_null {
+        Nullable::null()
+    } else {
+        Nullable::new(value)
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A date parameter, bound as `DATE`. Dates are proleptic Gregorian, so dates before the
+/// introduction of the Gregorian calendar are passed as is. Binds `NULL` if `is_null` is `TRUE`,
+/// in which case the other arguments are ignored.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_date_make(
+    year: i16,
+    month: u8,
+    day: u8,
+    is_null: bool,
+) -> *mut ArrowOdbcParameter {
+    let parameter = if is_null {
+        Nullable::null()
     } else {
-        Some(slice::from_raw_parts(char_buf, char_len))
+        Nullable::new(Date {
+            year,
+            month: month.into(),
+            day: day.into(),
+        })
+    };
+    ArrowOdbcParameter::new(parameter).into_raw()
+}
+
+/// A decimal parameter, bound as `NUMERIC` with the precision and scale of the value, so no digits
+/// are lost to floating point. The value is `mantissa * 10^-scale`, e.g. a mantissa of `-12345`
+/// with a scale of `2` is `-123.45`.
+///
+/// # Safety
+///
+/// `mantissa_buf` must point to at least `mantissa_len` bytes, holding the mantissa as decimal
+/// digits with an optional leading sign. The digits are copied into the parameter. `parameter_out`
+/// must point to a valid pointer. In case of success it is assigned an instance of
+/// `ArrowOdbcParameter`. Ownership is transferred to the caller.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_parameter_decimal_make(
+    mantissa_buf: *const u8,
+    mantissa_len: usize,
+    scale: i16,
+    parameter_out: *mut *mut ArrowOdbcParameter,
+) -> *mut ArrowOdbcError {
+    let mantissa = slice::from_raw_parts(mantissa_buf, mantissa_len);
+    let parameter = try_!(DecimalParameter::new(mantissa, scale));
+    *parameter_out = ArrowOdbcParameter::new(parameter).into_raw();
+    null_mut()
+}
+
+/// Largest precision a decimal parameter may have.
+const MAX_PRECISION: usize = 38;
+
+/// Input parameter holding a decimal as text, e.g. `-123.45`, described as `NUMERIC` to the driver.
+/// Binding `SQL_C_NUMERIC` instead would not work: `SQLBindParameter` resets the scale of the
+/// application descriptor to `0`, so drivers would ignore the scale within `SQL_NUMERIC_STRUCT`.
+struct DecimalParameter {
+    text: Vec<u8>,
+    indicator: isize,
+    precision: usize,
+    scale: i16,
+}
+
+impl DecimalParameter {
+    fn new(mantissa: &[u8], scale: i16) -> Result<Self, String> {
+        let text = str::from_utf8(mantissa).map_err(|e| e.to_string())?;
+        let (sign, digits) = match text.as_bytes().first() {
+            Some(b'-') => ("-", &text[1..]),
+            Some(b'+') => ("", &text[1..]),
+            _ => ("", text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Mantissa '{text}' of decimal parameter is not a number."));
+        }
+        // Leading zeroes do not count towards the precision, yet at least one digit is required.
+        let digits = digits.trim_start_matches('0');
+        let precision = digits.len().max(1).max(scale.max(0) as usize);
+        if !(0..=MAX_PRECISION as i16).contains(&scale) || precision > MAX_PRECISION {
+            return Err(format!(
+                "Decimal parameter with mantissa '{text}' and scale {scale} exceeds the maximum \
+                precision of {MAX_PRECISION}."
+            ));
+        }
+        // Pad with zeroes, so there is at least one digit in front of the decimal point.
+        let scale_len = scale as usize;
+        let digits = format!("{digits:0>width$}", width = scale_len + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale_len);
+        let text = if fraction.is_empty() {
+            format!("{sign}{integer}")
+        } else {
+            format!("{sign}{integer}.{fraction}")
+        };
+        Ok(Self {
+            indicator: text.len() as isize,
+            text: text.into_bytes(),
+            precision,
+            scale,
+        })
+    }
+}
+
+unsafe impl CData for DecimalParameter {
+    fn cdata_type(&self) -> CDataType {
+        CDataType::Char
+    }
+
+    fn indicator_ptr(&self) -> *const isize {
+        &self.indicator
+    }
+
+    fn value_ptr(&self) -> *const c_void {
+        self.text.as_ptr() as *const c_void
+    }
+
+    fn buffer_length(&self) -> isize {
+        self.text.len() as isize
+    }
+}
+
+impl HasDataType for DecimalParameter {
+    fn data_type(&self) -> DataType {
+        DataType::Numeric {
+            precision: self.precision,
+            scale: self.scale,
+        }
+    }
+}
+
+/// A parameter binding `SQL_DEFAULT_PARAM` as indicator. This causes the column to fall back to
+/// its default value, e.g. in an `INSERT` statement. Not all drivers support this, in which case
+/// executing the statement fails.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_default_make() -> *mut ArrowOdbcParameter {
+    ArrowOdbcParameter::new(IndicatorParameter {
+        indicator: SQL_DEFAULT_PARAM,
+        // The value is never read by the driver, yet some drivers reject a column size of zero.
+        data_type: DataType::Varchar { length: 1 },
+    })
+    .into_raw()
+}
+
+/// A `NULL` parameter of the given SQL type. Some drivers, e.g. for Oracle, require the type of a
+/// `NULL` to match the column it is compared to or inserted into. `sql_data_type` is the code of
+/// the type as defined by ODBC, e.g. `12` for `SQL_VARCHAR`, `4` for `SQL_INTEGER`, `-5` for
+/// `SQL_BIGINT`, `8` for `SQL_DOUBLE`, `91` for `SQL_TYPE_DATE` or `93` for `SQL_TYPE_TIMESTAMP`.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_parameter_null_make(sql_data_type: i16) -> *mut ArrowOdbcParameter {
+    ArrowOdbcParameter::new(IndicatorParameter {
+        indicator: NULL_DATA,
+        // The value is never read by the driver, yet some drivers reject a column size of zero.
+        data_type: DataType::new(SqlDataType(sql_data_type), 1, 0),
+    })
+    .into_raw()
+}
+
+/// Indicator value telling the driver to use the default value of the column.
+const SQL_DEFAULT_PARAM: isize = -5;
+
+/// Input parameter without a value, which only carries an indicator, like `NULL_DATA` or
+/// `SQL_DEFAULT_PARAM`.
+struct IndicatorParameter {
+    indicator: isize,
+    data_type: DataType,
+}
+
+unsafe impl CData for IndicatorParameter {
+    fn cdata_type(&self) -> CDataType {
+        CDataType::Char
+    }
+
+    fn indicator_ptr(&self) -> *const isize {
+        &self.indicator
+    }
+
+    fn value_ptr(&self) -> *const c_void {
+        null()
+    }
+
+    fn buffer_length(&self) -> isize {
+        0
+    }
+}
+
+impl HasDataType for IndicatorParameter {
+    fn data_type(&self) -> DataType {
+        self.data_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::null;
+
+    use arrow_odbc::odbc_api::{
+        handles::{CData, HasDataType},
+        sys::{CDataType, Date, NULL_DATA},
+        DataType,
     };
 
-    let param = ArrowOdbcParameter::from_opt_str(opt);
-    Box::into_raw(Box::new(param))
+    use super::{
+        arrow_odbc_parameter_binary_make, arrow_odbc_parameter_bool_make,
+        arrow_odbc_parameter_date_make, arrow_odbc_parameter_default_make,
+        arrow_odbc_parameter_f64_make, arrow_odbc_parameter_i64_make,
+        arrow_odbc_parameter_null_make, arrow_odbc_parameter_string_make, DecimalParameter,
+        SQL_DEFAULT_PARAM,
+    };
+
+    #[test]
+    fn empty_string_is_not_null() {
+        let text = b"";
+
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_string_make(text.as_ptr(), 0)) }.unwrap();
+
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn null_pointer_is_null() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_string_make(null(), 0)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn empty_binary_is_not_null() {
+        let bytes = b"";
+
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_binary_make(bytes.as_ptr(), 0, false)) }
+                .unwrap();
+
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn binary_null() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_binary_make(null(), 0, true)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn f64_is_bound_as_double() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_f64_make(0.1, false)) }.unwrap();
+
+        assert_eq!(DataType::Double, parameter.data_type());
+        assert_eq!(CDataType::Double, parameter.cdata_type());
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(0.1, unsafe { *(parameter.value_ptr() as *const f64) });
+    }
+
+    #[test]
+    fn f64_null() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_f64_make(0.0, true)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn i64_is_bound_as_bigint() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_i64_make(i64::MIN, false)) }.unwrap();
+
+        assert_eq!(DataType::BigInt, parameter.data_type());
+        assert_eq!(CDataType::SBigInt, parameter.cdata_type());
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(i64::MIN, unsafe { *(parameter.value_ptr() as *const i64) });
+    }
+
+    #[test]
+    fn i64_null() {
+        let parameter = unsafe { Box::from_raw(arrow_odbc_parameter_i64_make(0, true)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn bool_is_bound_as_bit() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_bool_make(true, false)) }.unwrap();
+
+        assert_eq!(DataType::Bit, parameter.data_type());
+        assert_eq!(CDataType::Bit, parameter.cdata_type());
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(1, unsafe { *(parameter.value_ptr() as *const u8) });
+    }
+
+    #[test]
+    fn bool_null() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_bool_make(false, true)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn default_parameter() {
+        let parameter = unsafe { Box::from_raw(arrow_odbc_parameter_default_make()) }.unwrap();
+
+        assert_eq!(SQL_DEFAULT_PARAM, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(DataType::Varchar { length: 1 }, parameter.data_type());
+        assert!(parameter.value_ptr().is_null());
+    }
+
+    #[test]
+    fn date_before_1900() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_date_make(1582, 10, 4, false)) }.unwrap();
+
+        assert_eq!(DataType::Date, parameter.data_type());
+        assert_eq!(CDataType::TypeDate, parameter.cdata_type());
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(
+            Date {
+                year: 1582,
+                month: 10,
+                day: 4
+            },
+            unsafe { *(parameter.value_ptr() as *const Date) }
+        );
+    }
+
+    #[test]
+    fn date_on_leap_day() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_date_make(2000, 2, 29, false)) }.unwrap();
+
+        assert_eq!(0, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(
+            Date {
+                year: 2000,
+                month: 2,
+                day: 29
+            },
+            unsafe { *(parameter.value_ptr() as *const Date) }
+        );
+    }
+
+    #[test]
+    fn date_null() {
+        let parameter =
+            unsafe { Box::from_raw(arrow_odbc_parameter_date_make(0, 0, 0, true)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn typed_null() {
+        // SQL_INTEGER
+        let parameter = unsafe { Box::from_raw(arrow_odbc_parameter_null_make(4)) }.unwrap();
+
+        assert_eq!(NULL_DATA, unsafe { *parameter.indicator_ptr() });
+        assert_eq!(DataType::Integer, parameter.data_type());
+    }
+
+    #[test]
+    fn negative_decimal() {
+        let parameter = DecimalParameter::new(b"-12345", 2).unwrap();
+
+        assert_eq!(
+            DataType::Numeric {
+                precision: 5,
+                scale: 2
+            },
+            parameter.data_type()
+        );
+        assert_eq!(b"-123.45", &parameter.text[..]);
+        assert_eq!(7, unsafe { *parameter.indicator_ptr() });
+    }
+
+    #[test]
+    fn decimal_smaller_than_one() {
+        let parameter = DecimalParameter::new(b"5", 3).unwrap();
+
+        assert_eq!(
+            DataType::Numeric {
+                precision: 3,
+                scale: 3
+            },
+            parameter.data_type()
+        );
+        assert_eq!(b"0.005", &parameter.text[..]);
+    }
+
+    #[test]
+    fn decimal_with_maximum_precision() {
+        let nines = "9".repeat(38);
+
+        let parameter = DecimalParameter::new(nines.as_bytes(), 0).unwrap();
+
+        assert_eq!(
+            DataType::Numeric {
+                precision: 38,
+                scale: 0
+            },
+            parameter.data_type()
+        );
+        assert_eq!(nines.as_bytes(), &parameter.text[..]);
+    }
+
+    #[test]
+    fn decimal_exceeding_precision() {
+        let digits = "1".repeat(39);
+
+        assert!(DecimalParameter::new(digits.as_bytes(), 0).is_err());
+    }
 }
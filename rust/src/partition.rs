@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use arrow_odbc::arrow::{
+    array::UInt32Array,
+    compute::{
+        kernels::{partition::lexicographical_partition_ranges, sort::lexsort_to_indices},
+        take, SortColumn,
+    },
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+
+/// Splits `batch` into one batch per distinct value of the column at `column_index`. Rows within
+/// each partition keep their relative order. `NULL`s form a partition of their own.
+pub fn partition(batch: &RecordBatch, column_index: usize) -> Result<Vec<RecordBatch>, ArrowError> {
+    // Sorting by the original row index second, keeps the order of the rows within a partition
+    // stable.
+    let row_index = UInt32Array::from_iter_values(0..batch.num_rows() as u32);
+    let sort_columns = [
+        SortColumn {
+            values: batch.column(column_index).clone(),
+            options: None,
+        },
+        SortColumn {
+            values: Arc::new(row_index),
+            options: None,
+        },
+    ];
+    let indices = lexsort_to_indices(&sort_columns, None)?;
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &indices, None))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sorted = RecordBatch::try_new(batch.schema(), columns)?;
+
+    let ranges = lexicographical_partition_ranges(&[SortColumn {
+        values: sorted.column(column_index).clone(),
+        options: None,
+    }])?;
+    let partitions = ranges
+        .map(|range| sorted.slice(range.start, range.end - range.start))
+        .collect();
+    Ok(partitions)
+}
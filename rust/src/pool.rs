@@ -0,0 +1,85 @@
+use std::{
+    ptr::null_mut,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use arrow_odbc::odbc_api::{
+    self,
+    sys::{AttrConnectionPooling, AttrCpMatch},
+    Environment,
+};
+
+use crate::{try_, ArrowOdbcError};
+
+/// `true` if pooled connections are only reused, if their connection strings match exactly.
+static STRICT_CP_MATCH: AtomicBool = AtomicBool::new(true);
+
+/// Set once the ODBC environment shared by default has been allocated. Settings applied to it at
+/// allocation can not change anymore afterwards.
+static ENVIRONMENT_ALLOCATED: AtomicBool = AtomicBool::new(false);
+
+/// Allocates the ODBC environment shared by default. Connection pool matching can not be configured
+/// anymore afterwards.
+pub fn allocate_shared_environment() -> Result<Environment, odbc_api::Error> {
+    ENVIRONMENT_ALLOCATED.store(true, Ordering::SeqCst);
+    allocate_environment()
+}
+
+/// Allocates an ODBC environment, applying the connection pool matching configured so far.
+pub fn allocate_environment() -> Result<Environment, odbc_api::Error> {
+    let mut environment = Environment::new()?;
+    let matching = if STRICT_CP_MATCH.load(Ordering::SeqCst) {
+        AttrCpMatch::Strict
+    } else {
+        AttrCpMatch::Relaxed
+    };
+    environment.set_connection_pooling_matching(matching)?;
+    Ok(environment)
+}
+
+/// Reduce overhead of opening connections by reusing them. Pooling is driver aware, i.e. each
+/// driver manages its own pool of connections.
+///
+/// # Safety
+///
+/// Pooling is determined at the time the ODBC environment is allocated, which happens with the
+/// first connection opened in the process. Changing the pooling setting afterwards is undefined
+/// behaviour according to the ODBC standard, so this should be called before any connection is
+/// opened.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_enable_connection_pooling() -> *mut ArrowOdbcError {
+    try_!(Environment::set_connection_pooling(
+        AttrConnectionPooling::DriverAware
+    ));
+    null_mut()
+}
+
+/// Switches connection pooling off again. Off is the default.
+///
+/// # Safety
+///
+/// Same as for [`arrow_odbc_enable_connection_pooling`]. Changing the pooling setting after
+/// connections have been opened is undefined behaviour according to the ODBC standard.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_disable_connection_pooling() -> *mut ArrowOdbcError {
+    try_!(Environment::set_connection_pooling(AttrConnectionPooling::Off));
+    null_mut()
+}
+
+/// Controls how closely connection attributes must match, in order for a pooled connection to be
+/// reused. `strict` (the default) requires the connection strings to match exactly. Otherwise the
+/// matching is relaxed and up to the driver.
+///
+/// Must be called before the first connection is opened, as the setting is applied to the ODBC
+/// environment once it is allocated.
+#[no_mangle]
+pub extern "C" fn arrow_odbc_set_cp_match(strict: bool) -> *mut ArrowOdbcError {
+    if ENVIRONMENT_ALLOCATED.load(Ordering::SeqCst) {
+        return ArrowOdbcError::new(
+            "Connection pool matching must be configured before opening the first connection.",
+        )
+        .into_raw();
+    }
+    STRICT_CP_MATCH.store(strict, Ordering::SeqCst);
+    null_mut()
+}
@@ -0,0 +1,172 @@
+use std::{
+    ptr::{null_mut, NonNull},
+    slice, str,
+    sync::{Arc, Mutex},
+};
+
+use arrow_odbc::odbc_api::{
+    handles::{AsStatementRef, StatementRef},
+    CursorImpl, Environment, Prepared, StatementConnection,
+};
+
+use crate::{
+    parameter::{parameters_from_raw, ArrowOdbcParameter},
+    reader::{reader_from_cursor, ArrowOdbcReader},
+    try_, ArrowOdbcError, OdbcConnection,
+};
+
+/// Opaque type holding a statement, which has been prepared once and can be executed repeatedly
+/// with different parameters. This type also has ownership of the ODBC Connection handle and
+/// shares ownership of the environment it has been opened with.
+// SAFETY: The connection within the slot borrows the environment for `'static`. Readers lent the
+// statement hold their own share of it. Here `Arc<Environment>` must stay declared after the slot,
+// so it is dropped last.
+pub struct ArrowOdbcPreparedStatement(Arc<Slot>, Arc<Environment>);
+
+/// Holds the prepared statement, unless it is lent to the reader of an execution.
+type Slot = Mutex<Option<Prepared<StatementConnection<'static>>>>;
+
+/// Owns the prepared statement as long as the cursor of an execution is open. Hands it back to the
+/// slot of the `ArrowOdbcPreparedStatement` once dropped, so it can be executed again.
+struct LentStatement {
+    prepared: Option<Prepared<StatementConnection<'static>>>,
+    slot: Arc<Slot>,
+}
+
+impl AsStatementRef for LentStatement {
+    fn as_stmt_ref(&mut self) -> StatementRef<'_> {
+        self.prepared.as_mut().unwrap().as_stmt_ref()
+    }
+}
+
+impl Drop for LentStatement {
+    fn drop(&mut self) {
+        // The slot is released with the statement, if it has been freed in the meantime.
+        *self.slot.lock().unwrap() = self.prepared.take();
+    }
+}
+
+/// Prepares a statement, so the data source parses and plans it only once, rather than for each
+/// execution.
+///
+/// Takes ownership of connection even in case of an error.
+///
+/// # Safety
+///
+/// * `connection` must point to a valid OdbcConnection. This function takes ownership of the
+///   connection, even in case of an error. So The connection must not be freed explicitly
+///   afterwards.
+/// * `query_buf` must point to a valid utf-8 string
+/// * `query_len` describes the len of `query_buf` in bytes.
+/// * `statement_out` in case of success this will point to an instance of
+///   `ArrowOdbcPreparedStatement`. Ownership is transferred to the caller.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_connection_prepare(
+    connection: NonNull<OdbcConnection>,
+    query_buf: *const u8,
+    query_len: usize,
+    statement_out: *mut *mut ArrowOdbcPreparedStatement,
+) -> *mut ArrowOdbcError {
+    let query = slice::from_raw_parts(query_buf, query_len);
+    let query = str::from_utf8(query).unwrap();
+
+    let connection = *Box::from_raw(connection.as_ptr());
+
+    let prepared = try_!(connection.0.into_prepared(query));
+    let slot = Arc::new(Mutex::new(Some(prepared)));
+    *statement_out = Box::into_raw(Box::new(ArrowOdbcPreparedStatement(slot, connection.1)));
+    null_mut() // Ok(())
+}
+
+/// Frees the resources associated with an ArrowOdbcPreparedStatement
+///
+/// # Safety
+///
+/// `statement` must point to a valid ArrowOdbcPreparedStatement. A reader created by the last
+/// execution of the statement may still be alive. It keeps the statement open until it is freed.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_prepared_statement_free(
+    statement: NonNull<ArrowOdbcPreparedStatement>,
+) {
+    drop(Box::from_raw(statement.as_ptr()));
+}
+
+/// Executes the prepared statement with the parameters passed. The statement may be executed
+/// again, once the reader of the previous execution has been freed. Executing it while that reader
+/// is still alive returns an error. The remaining arguments are the ones of
+/// [`crate::arrow_odbc_reader_make`].
+///
+/// # Safety
+///
+/// * `statement` must point to a valid ArrowOdbcPreparedStatement. Ownership is not transferred.
+/// * `parameters` must contain only valid pointers. This function takes ownership of all of them
+///   independent if the function succeeds or not. Yet it does not take ownership of the array
+///   itself. Their number must match the number of placeholders in the statement.
+/// * `reader_out` in case of success this will point to an instance of `ArrowOdbcReader`, or
+///   `NULL` if the statement does not produce a result set. Ownership is transferred to the
+///   caller. The reader owns the statement until it is freed, even if it is converted into a
+///   stream.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_prepared_execute(
+    statement: NonNull<ArrowOdbcPreparedStatement>,
+    parameters: *const *mut ArrowOdbcParameter,
+    parameters_len: usize,
+    batch_size: usize,
+    max_text_size: usize,
+    max_binary_size: usize,
+    fallibale_allocations: bool,
+    decimal_as_text: bool,
+    bit_as_boolean: bool,
+    raw_binary_columns: *const usize,
+    raw_binary_columns_len: usize,
+    reader_out: *mut *mut ArrowOdbcReader,
+) -> *mut ArrowOdbcError {
+    let slot = &statement.as_ref().0;
+    let parameters = parameters_from_raw(parameters, parameters_len);
+
+    let taken = slot.lock().unwrap().take();
+    let mut prepared = match taken {
+        Some(prepared) => prepared,
+        None => {
+            return ArrowOdbcError::new(
+                "The statement is still in use by the reader of its previous execution. Free the \
+                reader before executing the statement again.",
+            )
+            .into_raw()
+        }
+    };
+    // Forget about the borrowed cursor without closing it. It is reopened below, owning the
+    // statement.
+    let has_cursor = match prepared.execute(&parameters[..]) {
+        Ok(maybe_cursor) => maybe_cursor.map(CursorImpl::into_stmt).is_some(),
+        Err(error) => {
+            *slot.lock().unwrap() = Some(prepared);
+            return ArrowOdbcError::new(error).into_raw();
+        }
+    };
+    if has_cursor {
+        let lent = LentStatement {
+            prepared: Some(prepared),
+            slot: slot.clone(),
+        };
+        // Safe, since the statement has just been executed and has an open cursor.
+        let cursor = CursorImpl::new(lent);
+        reader_from_cursor(
+            cursor,
+            statement.as_ref().1.clone(),
+            batch_size,
+            max_text_size,
+            max_binary_size,
+            fallibale_allocations,
+            decimal_as_text,
+            bit_as_boolean,
+            raw_binary_columns,
+            raw_binary_columns_len,
+            reader_out,
+        )
+    } else {
+        *slot.lock().unwrap() = Some(prepared);
+        *reader_out = null_mut();
+        null_mut() // Ok(())
+    }
+}
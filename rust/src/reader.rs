@@ -1,7 +1,13 @@
 use std::{
+    borrow::Cow,
+    cmp::min,
+    collections::VecDeque,
     ffi::c_void,
+    fs::File,
+    io::{self, Write},
     mem::swap,
     os::raw::c_int,
+    path::Path,
     ptr::{null_mut, NonNull},
     slice, str,
     sync::Arc,
@@ -10,18 +16,189 @@ use std::{
 use arrow_odbc::{
     arrow::{
         array::{Array, StructArray},
+        csv,
+        datatypes::{DataType, SchemaRef},
+        error::ArrowError,
         ffi::{FFI_ArrowArray, FFI_ArrowSchema},
-        record_batch::RecordBatchReader,
+        ffi_stream::{export_reader_into_raw, FFI_ArrowArrayStream},
+        ipc::writer::FileWriter,
+        record_batch::{RecordBatch, RecordBatchReader},
     },
-    odbc_api::{CursorImpl, StatementConnection},
-    OdbcReader, BufferAllocationOptions,
+    odbc_api::{Cursor, Environment},
+    arrow_schema_from, OdbcReader, BufferAllocationOptions,
 };
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
 
-use crate::{try_, ArrowOdbcError, OdbcConnection, parameter::ArrowOdbcParameter};
+use crate::{
+    parameter::{parameters_from_raw, ArrowOdbcParameter},
+    partition::partition,
+    schema::{booleans_as_int8, decimals_as_text, raw_binary, unsigned_integers},
+    transform::{
+        char_as_bool, downcast_integers, empty_strings_as_null, fixed_size_list, mask,
+        pair_as_map, parse_pg_arrays, rename, reorder, schema_metadata, Mask, Transform,
+    },
+    strings_from_raw, try_, ArrowOdbcError, OdbcConnection,
+};
 
 /// Opaque type holding all the state associated with an ODBC reader implementation in Rust. This
 /// type also has ownership of the ODBC Connection handle.
-pub struct ArrowOdbcReader(OdbcReader<CursorImpl<StatementConnection<'static>>>);
+pub struct ArrowOdbcReader {
+    /// Fetches the batches from the data source.
+    reader: Box<dyn RecordBatchReader>,
+    /// Applied in order to each fetched batch.
+    transforms: Vec<Transform>,
+    /// Schema of the batches handed out, i.e. after all transformations have been applied.
+    schema: SchemaRef,
+    /// Index of the column each fetched batch is partitioned by, if any.
+    partition_by: Option<usize>,
+    /// Upper bound for the number of rows in each batch handed out, if any.
+    max_batch_rows: Option<usize>,
+    /// Narrow `Int64` columns of the batches handed out by [`arrow_odbc_reader_next`] to the
+    /// smallest integer type holding their values.
+    downcast_integers: bool,
+    /// Batches which have been fetched, but not yet been handed out.
+    pending: VecDeque<RecordBatch>,
+    /// Reports the number of rows fetched from the data source so far, if set.
+    progress: Option<Progress>,
+    /// Error raised while peeking for further batches. Handed out instead of the next batch.
+    deferred_error: Option<ArrowError>,
+    /// Environment the connection has been opened with. Declared last, so it is dropped after the
+    /// connection owned by `reader`.
+    _environment: Arc<Environment>,
+}
+
+/// Invokes a callback each time another `every_rows` rows have been fetched from the data source.
+struct Progress {
+    every_rows: usize,
+    callback: extern "C" fn(usize, usize),
+    /// Invoked with `context` once the callback is no longer needed.
+    release: extern "C" fn(usize),
+    /// Passed to `callback` and `release`, identifying the callback on the side of the caller.
+    context: usize,
+    /// Total number of rows fetched so far.
+    total_rows: usize,
+}
+
+impl Progress {
+    fn advance(&mut self, num_rows: usize) {
+        let reported = self.total_rows / self.every_rows;
+        self.total_rows += num_rows;
+        if self.total_rows / self.every_rows > reported {
+            (self.callback)(self.context, self.total_rows);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        (self.release)(self.context);
+    }
+}
+
+impl ArrowOdbcReader {
+    fn new(reader: impl RecordBatchReader + 'static, environment: Arc<Environment>) -> Self {
+        let schema = reader.schema();
+        Self {
+            reader: Box::new(reader),
+            transforms: Vec::new(),
+            schema,
+            partition_by: None,
+            max_batch_rows: None,
+            downcast_integers: false,
+            pending: VecDeque::new(),
+            progress: None,
+            deferred_error: None,
+            _environment: environment,
+        }
+    }
+
+    /// Splits a fetched and transformed batch into the batches handed out, according to
+    /// `partition_by` and `max_batch_rows`.
+    fn enqueue(&mut self, batch: RecordBatch) -> Result<(), ArrowError> {
+        let batches = if let Some(column_index) = self.partition_by {
+            partition(&batch, column_index)?
+        } else {
+            vec![batch]
+        };
+        for batch in batches {
+            if let Some(max_rows) = self.max_batch_rows {
+                // Slices share the buffers of the batch, so splitting does not copy any values.
+                let mut offset = 0;
+                while offset < batch.num_rows() {
+                    let len = min(max_rows, batch.num_rows() - offset);
+                    self.pending.push_back(batch.slice(offset, len));
+                    offset += len;
+                }
+            } else {
+                self.pending.push_back(batch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends a transformation producing batches with the `schema` specified.
+    fn push_transform(&mut self, (schema, transform): (SchemaRef, Transform)) {
+        self.schema = schema;
+        self.transforms.push(transform);
+    }
+
+    /// Fetches from the data source until at least one batch is pending. `None` if the result set
+    /// is exhausted and no batches are pending.
+    fn fill_pending(&mut self) -> Option<Result<(), ArrowError>> {
+        // Fetch further batches from the data source, should a fetched one not yield any batches,
+        // e.g. because it has been empty.
+        while self.pending.is_empty() {
+            let fetched = self.reader.next()?;
+            if let (Ok(batch), Some(progress)) = (&fetched, &mut self.progress) {
+                progress.advance(batch.num_rows());
+            }
+            let result = fetched
+                .and_then(|batch| self.transforms.iter().try_fold(batch, |batch, t| t(batch)))
+                .and_then(|batch| self.enqueue(batch));
+            if let Err(error) = result {
+                return Some(Err(error));
+            }
+        }
+        Some(Ok(()))
+    }
+
+    /// `true` if further batches follow. Fetches the next batch from the data source, unless one
+    /// is already pending. An error doing so is handed out by the next call to `next`, so it counts
+    /// as further batch.
+    fn has_more(&mut self) -> bool {
+        if self.deferred_error.is_some() {
+            return true;
+        }
+        match self.fill_pending() {
+            None => false,
+            Some(Ok(())) => true,
+            Some(Err(error)) => {
+                self.deferred_error = Some(error);
+                true
+            }
+        }
+    }
+}
+
+impl Iterator for ArrowOdbcReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.deferred_error.take() {
+            return Some(Err(error));
+        }
+        if let Err(error) = self.fill_pending()? {
+            return Some(Err(error));
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl RecordBatchReader for ArrowOdbcReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
 
 /// Creates an Arrow ODBC reader instance.
 ///
@@ -35,6 +212,10 @@ pub struct ArrowOdbcReader(OdbcReader<CursorImpl<StatementConnection<'static>>>)
 ///   afterwards.
 /// * `query_buf` must point to a valid utf-8 string
 /// * `query_len` describes the len of `query_buf` in bytes.
+/// * `query_tag_buf` may be `NULL`. If not, it must point to a valid utf-8 string, which is
+///   prepended to the query as an SQL comment. This helps identifying the query in the
+///   monitoring of the database.
+/// * `query_tag_len` describes the len of `query_tag_buf` in bytes.
 /// * `parameters` must contain only valid pointers. This function takes ownership of all of them
 ///   independent if the function succeeds or not. Yet it does not take ownership of the array
 ///   itself.
@@ -46,6 +227,15 @@ pub struct ArrowOdbcReader(OdbcReader<CursorImpl<StatementConnection<'static>>>)
 /// * `fallibale_allocations`: `TRUE` if allocations should return an error, `FALSE` if it is fine
 ///   to abort the process. Enabling might have a performance overhead, so it might be desirable to
 ///   disable it, if you know there is enough memory available.
+/// * `decimal_as_text`: `TRUE` if decimal columns should be read as text (`Utf8`), rather than
+///   Arrow decimals.
+/// * `bit_as_boolean`: `TRUE` if boolean columns (e.g. `BIT`) should be read as Arrow booleans.
+///   `FALSE` reads them as `Int8` instead.
+/// * `raw_binary_columns` may be `NULL`. If not, it must point to `raw_binary_columns_len` zero
+///   based column indices. These columns are fetched as `SQL_C_BINARY` into Arrow `Binary`,
+///   independent of their SQL type. `NULL` values are read as `NULL`. The buffers are sized after
+///   the column size reported by the driver, so values with a larger binary representation (e.g.
+///   of small precision `NUMERIC` columns) are truncated.
 /// * `reader_out` in case of success this will point to an instance of `ArrowOdbcReader`.
 ///   Ownership is transferred to the caller.
 #[no_mangle]
@@ -53,28 +243,76 @@ pub unsafe extern "C" fn arrow_odbc_reader_make(
     connection: NonNull<OdbcConnection>,
     query_buf: *const u8,
     query_len: usize,
+    query_tag_buf: *const u8,
+    query_tag_len: usize,
     batch_size: usize,
     parameters: *const *mut ArrowOdbcParameter,
     parameters_len: usize,
     max_text_size: usize,
     max_binary_size: usize,
     fallibale_allocations: bool,
+    decimal_as_text: bool,
+    bit_as_boolean: bool,
+    raw_binary_columns: *const usize,
+    raw_binary_columns_len: usize,
     reader_out: *mut *mut ArrowOdbcReader,
 ) -> *mut ArrowOdbcError {
     let query = slice::from_raw_parts(query_buf, query_len);
     let query = str::from_utf8(query).unwrap();
+    let query = if query_tag_buf.is_null() {
+        Cow::Borrowed(query)
+    } else {
+        let tag = slice::from_raw_parts(query_tag_buf, query_tag_len);
+        let tag = str::from_utf8(tag).unwrap();
+        Cow::Owned(tag_query(query, tag))
+    };
 
     let connection = *Box::from_raw(connection.as_ptr());
 
-    let parameters = if parameters.is_null() {
-        Vec::new()
+    let parameters = parameters_from_raw(parameters, parameters_len);
+
+    let maybe_cursor = try_!(connection.0.into_cursor(&query, &parameters[..]));
+    if let Some(cursor) = maybe_cursor {
+        reader_from_cursor(
+            cursor,
+            connection.1,
+            batch_size,
+            max_text_size,
+            max_binary_size,
+            fallibale_allocations,
+            decimal_as_text,
+            bit_as_boolean,
+            raw_binary_columns,
+            raw_binary_columns_len,
+            reader_out,
+        )
     } else {
-        slice::from_raw_parts(parameters, parameters_len)
-            .iter()
-            .map(|&p| Box::from_raw(p).unwrap())
-            .collect()
-    };
+        *reader_out = null_mut();
+        null_mut() // Ok(())
+    }
+}
 
+/// Binds buffers to the result set of `cursor` and assigns a reader fetching from it to
+/// `reader_out`. The reader keeps `environment`, which `cursor` has been opened with, alive. The
+/// remaining arguments are the ones of [`arrow_odbc_reader_make`].
+///
+/// # Safety
+///
+/// See [`arrow_odbc_reader_make`].
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn reader_from_cursor(
+    mut cursor: impl Cursor + 'static,
+    environment: Arc<Environment>,
+    batch_size: usize,
+    max_text_size: usize,
+    max_binary_size: usize,
+    fallibale_allocations: bool,
+    decimal_as_text: bool,
+    bit_as_boolean: bool,
+    raw_binary_columns: *const usize,
+    raw_binary_columns_len: usize,
+    reader_out: *mut *mut ArrowOdbcReader,
+) -> *mut ArrowOdbcError {
     let max_text_size = if max_text_size == 0 {
         None
     } else {
@@ -93,16 +331,34 @@ pub unsafe extern "C" fn arrow_odbc_reader_make(
         fallibale_allocations,
     };
 
-    let maybe_cursor = try_!(connection.0.into_cursor(query, &parameters[..]));
-    if let Some(cursor) = maybe_cursor {
-        let reader = try_!(OdbcReader::with(cursor, batch_size, None, buffer_allocation_options));
-        *reader_out = Box::into_raw(Box::new(ArrowOdbcReader(reader)))
-    } else {
-        *reader_out = null_mut()
+    let schema = try_!(arrow_schema_from(&mut cursor));
+    let mut schema = try_!(unsigned_integers(schema, &mut cursor));
+    if decimal_as_text {
+        schema = decimals_as_text(schema);
+    }
+    if !bit_as_boolean {
+        schema = booleans_as_int8(schema);
     }
+    if !raw_binary_columns.is_null() {
+        let indices = slice::from_raw_parts(raw_binary_columns, raw_binary_columns_len);
+        schema = try_!(raw_binary(schema, indices));
+    }
+    let reader = try_!(OdbcReader::with(
+        cursor,
+        batch_size,
+        Some(Arc::new(schema)),
+        buffer_allocation_options
+    ));
+    *reader_out = Box::into_raw(Box::new(ArrowOdbcReader::new(reader, environment)));
     null_mut() // Ok(())
 }
 
+/// Prepends `tag` as an SQL comment to the query. A `*/` within the tag would terminate the comment
+/// early, so it is broken up.
+fn tag_query(query: &str, tag: &str) -> String {
+    format!("/* {} */ {}", tag.replace("*/", "* /"), query)
+}
+
 /// Frees the resources associated with an ArrowOdbcReader
 ///
 /// # Safety
@@ -117,46 +373,72 @@ pub unsafe extern "C" fn arrow_odbc_reader_free(reader: NonNull<ArrowOdbcReader>
 ///
 /// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
 /// * `array_out` and `schema_out` must both point to valid pointers, which themselves may be null.
+/// * `has_more_out` may be `NULL`. If not, and a batch has been handed out, it is assigned `1` if
+///   further batches follow, or `0` if the batch is the last one. To find out, the next batch is
+///   fetched ahead of time, so this delays handing out the current one. An error fetching ahead is
+///   reported by the next call, rather than this one.
 #[no_mangle]
 pub unsafe extern "C" fn arrow_odbc_reader_next(
     mut reader: NonNull<ArrowOdbcReader>,
     array: *mut c_void,
     schema: *mut c_void,
     has_next_out: *mut c_int,
+    has_more_out: *mut c_int,
 ) -> *mut ArrowOdbcError {
     let schema = schema as *mut FFI_ArrowSchema;
     let array = array as *mut FFI_ArrowArray;
 
-    if let Some(result) = reader.as_mut().0.next() {
+    let reader = reader.as_mut();
+    if let Some(result) = reader.next() {
         *array = FFI_ArrowArray::empty();
         *schema = FFI_ArrowSchema::empty();
 
-        let batch = try_!(result);
-        let struct_array: StructArray = batch.into();
-
-        let (ffi_array_ptr, ffi_schema_ptr) = try_!(struct_array.to_raw());
-
-        // In order to avoid memory leaks we must convert both pointers returned by the  `to_raw`
-        // method. So we must back to `Arc` again, so they are freed at the end of this function
-        // call in order to avoid memory leaks. Furthermore it is the callers responsibility to
-        // provide us with the FFI_Arrow* structures to fill, and the caller maintains ownership
-        // over them.
-
-        let mut arc_schema = Arc::from_raw(ffi_schema_ptr);
-        let source_schema = Arc::get_mut(&mut arc_schema).unwrap();
-        swap(&mut *schema, source_schema);
-
-        let mut arc_array = Arc::from_raw(ffi_array_ptr);
-        let source_array = Arc::get_mut(&mut arc_array).unwrap();
-        swap(&mut *array, source_array);
+        let mut batch = try_!(result);
+        if reader.downcast_integers {
+            batch = try_!(downcast_integers(batch));
+        }
+        try_!(export_batch(batch, array, schema));
 
         *has_next_out = 1;
+        if !has_more_out.is_null() {
+            *has_more_out = reader.has_more() as c_int;
+        }
     } else {
         *has_next_out = 0;
     }
     null_mut()
 }
 
+/// Moves `batch` as a struct array into the FFI structures provided by the caller.
+///
+/// # Safety
+///
+/// `array` and `schema` must point to valid FFI structures. Their previous content is dropped.
+pub unsafe fn export_batch(
+    batch: RecordBatch,
+    array: *mut FFI_ArrowArray,
+    schema: *mut FFI_ArrowSchema,
+) -> Result<(), ArrowError> {
+    let struct_array: StructArray = batch.into();
+
+    let (ffi_array_ptr, ffi_schema_ptr) = struct_array.to_raw()?;
+
+    // In order to avoid memory leaks we must convert both pointers returned by the  `to_raw`
+    // method. So we must back to `Arc` again, so they are freed at the end of this function
+    // call in order to avoid memory leaks. Furthermore it is the callers responsibility to
+    // provide us with the FFI_Arrow* structures to fill, and the caller maintains ownership
+    // over them.
+
+    let mut arc_schema = Arc::from_raw(ffi_schema_ptr);
+    let source_schema = Arc::get_mut(&mut arc_schema).unwrap();
+    swap(&mut *schema, source_schema);
+
+    let mut arc_array = Arc::from_raw(ffi_array_ptr);
+    let source_array = Arc::get_mut(&mut arc_array).unwrap();
+    swap(&mut *array, source_array);
+    Ok(())
+}
+
 /// Retrieve the associated schema from a reader.
 #[no_mangle]
 pub unsafe extern "C" fn arrow_odbc_reader_schema(
@@ -165,10 +447,608 @@ pub unsafe extern "C" fn arrow_odbc_reader_schema(
 ) -> *mut ArrowOdbcError {
     let out_schema: *mut FFI_ArrowSchema = out_schema as *mut FFI_ArrowSchema;
 
-    let reader = &mut reader.as_mut().0;
+    let reader = reader.as_mut();
     let schema_ref = reader.schema();
     let schema = &*schema_ref;
     let schema_ffi = try_!(schema.try_into());
     *out_schema = schema_ffi;
     null_mut()
 }
+
+/// Consumes the reader and exports it as an Arrow C stream.
+///
+/// The stream always carries the schema inferred from the result set. An empty result set
+/// therefore yields a valid stream with zero batches, rather than no stream at all.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`]. This function
+///   takes ownership of the reader. So the reader must not be freed explicitly afterwards.
+/// * `stream_out` must point to a valid `FFI_ArrowArrayStream`. The caller is responsible for
+///   releasing the stream.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_into_stream(
+    reader: NonNull<ArrowOdbcReader>,
+    stream_out: *mut c_void,
+) {
+    let reader = *Box::from_raw(reader.as_ptr());
+    let stream_out = stream_out as *mut FFI_ArrowArrayStream;
+    export_reader_into_raw(Box::new(reader), stream_out);
+}
+
+/// Fetches all remaining batches of the reader and spills them to Arrow IPC files. Each batch is
+/// written into its own file within `dir`, named after the zero based index of the batch (e.g.
+/// `0000000000.arrow`). This way the batches never need to be held in memory at the same time.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `dir_buf` must point to a valid utf-8 string, naming an existing directory.
+/// * `dir_len` describes the len of `dir_buf` in bytes.
+/// * `num_batches_out` is assigned the number of files written.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_spill_to_dir(
+    mut reader: NonNull<ArrowOdbcReader>,
+    dir_buf: *const u8,
+    dir_len: usize,
+    num_batches_out: *mut usize,
+) -> *mut ArrowOdbcError {
+    let dir = slice::from_raw_parts(dir_buf, dir_len);
+    let dir = Path::new(str::from_utf8(dir).unwrap());
+
+    let reader = reader.as_mut();
+    let schema = reader.schema();
+
+    let mut num_batches = 0;
+    for result in reader {
+        let batch = try_!(result);
+        let file = try_!(File::create(dir.join(format!("{:010}.arrow", num_batches))));
+        let mut writer = try_!(FileWriter::try_new(file, &schema));
+        try_!(writer.write(&batch));
+        try_!(writer.finish());
+        num_batches += 1;
+    }
+
+    *num_batches_out = num_batches;
+    null_mut()
+}
+
+/// Fetches all remaining batches of the reader and writes them into a single parquet file. Batches
+/// are written as they are fetched, so the result set never needs to be held in memory at once.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `path_buf` must point to a valid utf-8 string, naming the file to create. An existing file is
+///   overwritten.
+/// * `path_len` describes the len of `path_buf` in bytes.
+/// * `row_group_size` maximum number of rows in each row group of the parquet file. Must be at
+///   least 1.
+/// * `num_rows_out` is assigned the number of rows written.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_to_parquet(
+    mut reader: NonNull<ArrowOdbcReader>,
+    path_buf: *const u8,
+    path_len: usize,
+    row_group_size: usize,
+    num_rows_out: *mut usize,
+) -> *mut ArrowOdbcError {
+    let path = slice::from_raw_parts(path_buf, path_len);
+    let path = Path::new(str::from_utf8(path).unwrap());
+
+    if row_group_size == 0 {
+        return ArrowOdbcError::new("Row groups must hold at least 1 row.").into_raw();
+    }
+
+    let reader = reader.as_mut();
+    let schema = reader.schema();
+
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(row_group_size)
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let file = try_!(File::create(path));
+    let mut writer = try_!(ArrowWriter::try_new(file, schema, Some(properties)));
+
+    let mut num_rows = 0;
+    for result in reader {
+        let batch = try_!(result);
+        try_!(writer.write(&batch));
+        num_rows += batch.num_rows();
+    }
+    try_!(writer.close());
+
+    *num_rows_out = num_rows;
+    null_mut()
+}
+
+/// Fetches all remaining batches of the reader and writes them into a CSV file. Batches are written
+/// as they are fetched, so the result set never needs to be held in memory at once.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `path_buf` must point to a valid utf-8 string, naming the file to create. An existing file is
+///   overwritten.
+/// * `path_len` describes the len of `path_buf` in bytes.
+/// * `delimiter` separates the fields, e.g. `b','`.
+/// * `header`: `TRUE` if the column names should be written as first line. No header is written
+///   for a result set without batches.
+/// * `num_rows_out` is assigned the number of rows written.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_to_csv(
+    mut reader: NonNull<ArrowOdbcReader>,
+    path_buf: *const u8,
+    path_len: usize,
+    delimiter: u8,
+    header: bool,
+    num_rows_out: *mut usize,
+) -> *mut ArrowOdbcError {
+    let path = slice::from_raw_parts(path_buf, path_len);
+    let path = Path::new(str::from_utf8(path).unwrap());
+
+    let mut file = TrackedWrite {
+        inner: try_!(File::create(path)),
+        error: None,
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(header)
+        .with_delimiter(delimiter)
+        .build(&mut file);
+
+    let mut num_rows = 0;
+    for result in reader.as_mut() {
+        let batch = try_!(result);
+        try_!(writer.write(&batch));
+        num_rows += batch.num_rows();
+    }
+    // Dropping the writer flushes its buffer, ignoring any error. So the error is taken from the
+    // file instead. Syncing also reports errors the operating system deferred, e.g. a full disk.
+    drop(writer);
+    if let Some(error) = file.error.take() {
+        return ArrowOdbcError::new(error).into_raw();
+    }
+    try_!(file.inner.sync_all());
+
+    *num_rows_out = num_rows;
+    null_mut()
+}
+
+/// Forwards to `inner`, remembering the first error. Errors occurring while a writer is flushed as
+/// part of being dropped are otherwise lost.
+struct TrackedWrite<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W> TrackedWrite<W> {
+    fn track<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        match &result {
+            // Interrupted writes are retried.
+            Err(error) if error.kind() != io::ErrorKind::Interrupted => {
+                self.error
+                    .get_or_insert_with(|| io::Error::new(error.kind(), error.to_string()));
+            }
+            _ => (),
+        }
+        result
+    }
+}
+
+impl<W: Write> Write for TrackedWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.track(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.inner.flush();
+        self.track(result)
+    }
+}
+
+/// Fetches all remaining batches without exporting them over the C boundary. `on_batch` is invoked
+/// with the number of rows of each batch, e.g. to report progress.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `on_batch` must be a valid function pointer.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_drain_with_callback(
+    mut reader: NonNull<ArrowOdbcReader>,
+    on_batch: extern "C" fn(usize),
+) -> *mut ArrowOdbcError {
+    let reader = reader.as_mut();
+    for result in reader {
+        let batch = try_!(result);
+        on_batch(batch.num_rows());
+    }
+    null_mut()
+}
+
+/// Invokes `callback` with `context` and the total number of rows fetched from the data source so
+/// far, each time another `every_rows` rows have been fetched. The callback is invoked on the
+/// thread fetching the batches, from within the call fetching them, independent of how the batches
+/// are consumed. Once the reader, or a stream it has been converted into, is freed `release` is
+/// invoked with `context`, so the caller knows the callback is no longer needed.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `callback` must be a valid function pointer, until `release` has been invoked.
+/// * `release` must be a valid function pointer, for as long as the reader is alive. It is not
+///   invoked, if this function returns an error.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_set_progress_callback(
+    mut reader: NonNull<ArrowOdbcReader>,
+    every_rows: usize,
+    callback: extern "C" fn(usize, usize),
+    release: extern "C" fn(usize),
+    context: usize,
+) -> *mut ArrowOdbcError {
+    if every_rows == 0 {
+        return ArrowOdbcError::new("Progress must be reported at least every 1 row.").into_raw();
+    }
+    reader.as_mut().progress = Some(Progress {
+        every_rows,
+        callback,
+        release,
+        context,
+        total_rows: 0,
+    });
+    null_mut()
+}
+
+/// Fetches all remaining batches and exports them as an Arrow C stream. In contrast to
+/// [`arrow_odbc_reader_into_stream`] all batches are fetched before this function returns, and the
+/// stream merely hands out batches already held in memory. The reader itself is not consumed.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `stream_out` must point to a valid `FFI_ArrowArrayStream`. The caller is responsible for
+///   releasing the stream.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_read_all(
+    mut reader: NonNull<ArrowOdbcReader>,
+    stream_out: *mut c_void,
+) -> *mut ArrowOdbcError {
+    let reader = reader.as_mut();
+    let schema = reader.schema();
+    let batches = try_!(reader.collect::<Result<Vec<_>, _>>());
+
+    let fetched = FetchedBatches {
+        schema,
+        batches: batches.into_iter(),
+    };
+    let stream_out = stream_out as *mut FFI_ArrowArrayStream;
+    export_reader_into_raw(Box::new(fetched), stream_out);
+    null_mut()
+}
+
+/// Batches which have already been fetched from the data source and are held in memory.
+struct FetchedBatches {
+    schema: SchemaRef,
+    batches: std::vec::IntoIter<RecordBatch>,
+}
+
+impl Iterator for FetchedBatches {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.batches.next().map(Ok)
+    }
+}
+
+impl RecordBatchReader for FetchedBatches {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Splits each batch fetched from the data source into one batch per distinct value of the column
+/// at `column_index`. Rows within each partition keep their original order. Partitioning happens
+/// within a fetched batch only, so the same value may appear in several emitted batches, if it
+/// spans the boundary of a fetch.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_partition_by(
+    mut reader: NonNull<ArrowOdbcReader>,
+    column_index: usize,
+) -> *mut ArrowOdbcError {
+    let reader = reader.as_mut();
+    let num_columns = reader.schema().fields().len();
+    if column_index >= num_columns {
+        return ArrowOdbcError::new(format!(
+            "Can not partition by column {column_index}. The result set has only {num_columns} \
+            columns."
+        ))
+        .into_raw();
+    }
+    reader.partition_by = Some(column_index);
+    null_mut()
+}
+
+/// Guarantees that no batch handed out holds more than `max_rows` rows. Larger batches are split
+/// into slices sharing the buffers of the fetched batch, so no values are copied. In contrast to
+/// the batch size passed to [`arrow_odbc_reader_make`] this also bounds the batches emitted by
+/// [`arrow_odbc_reader_partition_by`] or transformations.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_set_max_batch_rows(
+    mut reader: NonNull<ArrowOdbcReader>,
+    max_rows: usize,
+) -> *mut ArrowOdbcError {
+    if max_rows == 0 {
+        return ArrowOdbcError::new("Maximum number of rows in a batch must be at least 1.")
+            .into_raw();
+    }
+    reader.as_mut().max_batch_rows = Some(max_rows);
+    null_mut()
+}
+
+/// Replaces the key column with a column of maps, each holding a single entry made up of the key
+/// and value of the row. The value column is removed. Rows with a `NULL` key yield a `NULL` map.
+/// Column indices are zero based and refer to the schema before this transformation.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_pair_columns_as_map(
+    mut reader: NonNull<ArrowOdbcReader>,
+    key_index: usize,
+    value_index: usize,
+) -> *mut ArrowOdbcError {
+    let reader = reader.as_mut();
+    let transform = try_!(pair_as_map(&reader.schema(), key_index, value_index));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Emits the columns at the zero based indices in `order`, in the order specified. Columns may be
+/// omitted or repeated.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `order` must point to an array of `order_len` column indices.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_reorder_columns(
+    mut reader: NonNull<ArrowOdbcReader>,
+    order: *const usize,
+    order_len: usize,
+) -> *mut ArrowOdbcError {
+    let order = slice::from_raw_parts(order, order_len).to_vec();
+    let reader = reader.as_mut();
+    let transform = try_!(reorder(&reader.schema(), order));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Parses the text in the column at the zero based `column_index` as PostgreSQL array literals
+/// (e.g. `{1,2,NULL}`) and emits lists instead. The elements are cast from text to `element_type`,
+/// or stay strings if it is `NULL`. Elements which can not be cast become `NULL`. Only one
+/// dimensional arrays are supported, nested arrays are reported as an error. If `large` is `TRUE`
+/// the column is emitted as `LargeList`, whose 64 Bit offsets can not overflow for big batches.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `element_type` may be `NULL`. If not, it must point to an Arrow schema describing the type
+///   of the elements. Ownership is not transferred.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_parse_pg_arrays(
+    mut reader: NonNull<ArrowOdbcReader>,
+    column_index: usize,
+    element_type: *const c_void,
+    large: bool,
+) -> *mut ArrowOdbcError {
+    let element_type = if element_type.is_null() {
+        None
+    } else {
+        let element_type = element_type as *const FFI_ArrowSchema;
+        Some(try_!(DataType::try_from(&*element_type)))
+    };
+    let reader = reader.as_mut();
+    let transform = try_!(parse_pg_arrays(
+        &reader.schema(),
+        column_index,
+        element_type,
+        large
+    ));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Assigns names to the columns of the batches, rather than using the ones reported by the data
+/// source. Entries which are `NULL` or empty keep the name reported by the data source.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `names` must point to `count` pointers, each either `NULL` or pointing to a valid utf-8
+///   string. It may be `NULL` if `count` is `0`.
+/// * `name_lens` must point to `count` lengths in bytes of the respective names. It may be `NULL`
+///   if `count` is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_rename_columns(
+    mut reader: NonNull<ArrowOdbcReader>,
+    names: *const *const u8,
+    name_lens: *const usize,
+    count: usize,
+) -> *mut ArrowOdbcError {
+    let names = try_!(strings_from_raw(names, name_lens, count))
+        .into_iter()
+        .map(|name| name.filter(|name| !name.is_empty()).map(str::to_owned))
+        .collect();
+
+    let reader = reader.as_mut();
+    let transform = try_!(rename(&reader.schema(), names));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Reads the single character text column at the zero based `column_index` as booleans, e.g. the
+/// `'Y'` and `'N'` flags of legacy schemas. Values other than `true_char` or `false_char` are read
+/// as `NULL`. Trailing spaces are ignored.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_char_as_bool(
+    mut reader: NonNull<ArrowOdbcReader>,
+    column_index: usize,
+    true_char: u8,
+    false_char: u8,
+) -> *mut ArrowOdbcError {
+    let reader = reader.as_mut();
+    let transform = try_!(char_as_bool(
+        &reader.schema(),
+        column_index,
+        true_char.into(),
+        false_char.into(),
+    ));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// If `enabled`, `Int64` columns of each batch handed out by [`arrow_odbc_reader_next`] are cast to
+/// the narrowest signed integer type able to hold all values of the batch. This happens per batch,
+/// so the type of a column may differ between batches and from the schema of the reader. Batches
+/// exported as stream, or written into files, are not affected.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_downcast_integers(
+    mut reader: NonNull<ArrowOdbcReader>,
+    enabled: bool,
+) {
+    reader.as_mut().downcast_integers = enabled;
+}
+
+/// Reads empty strings in text columns as `NULL`. Data sources like Oracle do not distinguish
+/// between the two, so this normalizes the data read from other sources to match.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_empty_strings_as_null(
+    mut reader: NonNull<ArrowOdbcReader>,
+) {
+    let reader = reader.as_mut();
+    let transform = empty_strings_as_null(&reader.schema());
+    reader.push_transform(transform);
+}
+
+/// Hides the values of the column at the zero based `column_index` before the batches are handed
+/// out, so e.g. personally identifiable information never leaves the native code.
+///
+/// `mode` is one of:
+///
+/// * `0`: Replaces each value with the hex encoded SHA-256 hash of its bytes. Values which are
+///   neither text nor binary are formatted as text before hashing.
+/// * `1`: Replaces all values with `NULL`.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_mask_column(
+    mut reader: NonNull<ArrowOdbcReader>,
+    column_index: usize,
+    mode: u8,
+) -> *mut ArrowOdbcError {
+    let mode = match mode {
+        0 => Mask::Sha256,
+        1 => Mask::Redact,
+        _ => return ArrowOdbcError::new(format!("Unknown mask mode {mode}.")).into_raw(),
+    };
+    let reader = reader.as_mut();
+    let transform = try_!(mask(&reader.schema(), column_index, mode));
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Attaches key value pairs as metadata to the schema of the batches, e.g. to record the
+/// provenance of the data. Existing entries with the same keys are replaced.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+/// * `keys` and `values` must each point to `count` pointers to valid utf-8 strings. They may be
+///   `NULL` if `count` is `0`.
+/// * `key_lens` and `value_lens` must each point to `count` lengths in bytes of the respective
+///   strings. They may be `NULL` if `count` is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_set_schema_metadata(
+    mut reader: NonNull<ArrowOdbcReader>,
+    keys: *const *const u8,
+    key_lens: *const usize,
+    values: *const *const u8,
+    value_lens: *const usize,
+    count: usize,
+) -> *mut ArrowOdbcError {
+    let keys = try_!(strings_from_raw(keys, key_lens, count));
+    let values = try_!(strings_from_raw(values, value_lens, count));
+    let metadata = keys
+        .into_iter()
+        .zip(values)
+        .map(|(key, value)| {
+            (
+                key.unwrap_or_default().to_owned(),
+                value.unwrap_or_default().to_owned(),
+            )
+        })
+        .collect();
+
+    let reader = reader.as_mut();
+    let transform = schema_metadata(&reader.schema(), metadata);
+    reader.push_transform(transform);
+    null_mut()
+}
+
+/// Reinterprets the column at the zero based `column_index` as fixed size lists of `size` floating
+/// point numbers, e.g. embeddings. Text is parsed as comma separated numbers, optionally enclosed
+/// in brackets (e.g. `[0.5,1,2]`). Binary is read as `size` consecutive little endian floats.
+///
+/// `element_type` is `0` for `Float32` and `1` for `Float64` elements.
+///
+/// # Safety
+///
+/// * `reader` must be valid non-null reader, allocated by [`arrow_odbc_reader_make`].
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_reader_column_as_fixed_list(
+    mut reader: NonNull<ArrowOdbcReader>,
+    column_index: usize,
+    element_type: u8,
+    size: usize,
+) -> *mut ArrowOdbcError {
+    let element_type = match element_type {
+        0 => DataType::Float32,
+        1 => DataType::Float64,
+        other => {
+            return ArrowOdbcError::new(format!("Unknown element type {other}.")).into_raw();
+        }
+    };
+    let reader = reader.as_mut();
+    let transform = try_!(fixed_size_list(
+        &reader.schema(),
+        column_index,
+        element_type,
+        size
+    ));
+    reader.push_transform(transform);
+    null_mut()
+}
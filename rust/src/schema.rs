@@ -0,0 +1,125 @@
+//! Adjustments to the Arrow schema inferred by `arrow-odbc`, before it is used to bind the buffers
+//! of the reader.
+
+use arrow_odbc::{
+    arrow::{
+        datatypes::{DataType, Field, Schema},
+        error::ArrowError,
+    },
+    odbc_api::{self, ResultSetMetadata},
+};
+
+/// Drivers report unsigned integer columns using the same SQL types as signed ones. Replace the
+/// signed Arrow integer of each column the driver flags as unsigned, with a type able to hold all
+/// of its values.
+pub fn unsigned_integers(
+    schema: Schema,
+    cursor: &mut impl ResultSetMetadata,
+) -> Result<Schema, odbc_api::Error> {
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    for (index, field) in schema.fields().iter().enumerate() {
+        let unsigned = match unsigned_type(field.data_type()) {
+            Some(unsigned) => unsigned,
+            None => {
+                fields.push(field.clone());
+                continue;
+            }
+        };
+        // ODBC column indices are one based.
+        let column_number = (index + 1) as u16;
+        if cursor.column_is_unsigned(column_number)? {
+            fields.push(Field::new(field.name(), unsigned, field.is_nullable()));
+        } else {
+            fields.push(field.clone());
+        }
+    }
+    Ok(Schema::new(fields))
+}
+
+/// Type to read an unsigned integer column into, which `arrow-odbc` infers as `data_type`. `None`
+/// if `data_type` is not an integer. `arrow-odbc` binds no unsigned integer wider than `UInt8`, so
+/// wider columns are read into the next larger signed integer. Unsigned 64 Bit integers have up to
+/// 20 digits and are read as decimals.
+fn unsigned_type(data_type: &DataType) -> Option<DataType> {
+    let unsigned = match data_type {
+        DataType::Int8 => DataType::UInt8,
+        DataType::Int16 => DataType::Int32,
+        DataType::Int32 => DataType::Int64,
+        DataType::Int64 => DataType::Decimal128(20, 0),
+        _ => return None,
+    };
+    Some(unsigned)
+}
+
+/// Read decimal columns as text, rather than as Arrow decimals. The driver formats the values, so
+/// the digits are exactly the ones reported by the data source.
+pub fn decimals_as_text(schema: Schema) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Decimal128(_, _) => {
+                Field::new(field.name(), DataType::Utf8, field.is_nullable())
+            }
+            _ => field.clone(),
+        })
+        .collect();
+    Schema::new(fields)
+}
+
+/// Read boolean (e.g. `BIT`) columns as `Int8`, like earlier versions did.
+pub fn booleans_as_int8(schema: Schema) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Boolean => Field::new(field.name(), DataType::Int8, field.is_nullable()),
+            _ => field.clone(),
+        })
+        .collect();
+    Schema::new(fields)
+}
+
+/// Read the columns at the zero based `indices` as `Binary`, independent of their SQL type. The
+/// driver is asked to deliver the values as `SQL_C_BINARY`, i.e. the bytes of its own C
+/// representation of the value.
+pub fn raw_binary(schema: Schema, indices: &[usize]) -> Result<Schema, ArrowError> {
+    let mut fields = schema.fields().clone();
+    for &index in indices {
+        let field = fields.get_mut(index).ok_or_else(|| {
+            ArrowError::InvalidArgumentError(format!(
+                "Column index {index} is out of range. The result set has only {} columns.",
+                schema.fields().len()
+            ))
+        })?;
+        *field = Field::new(field.name(), DataType::Binary, field.is_nullable());
+    }
+    Ok(Schema::new(fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_odbc::arrow::datatypes::DataType;
+
+    use super::unsigned_type;
+
+    #[test]
+    fn unsigned_tinyint_is_uint8() {
+        assert_eq!(Some(DataType::UInt8), unsigned_type(&DataType::Int8));
+    }
+
+    #[test]
+    fn wider_unsigned_integers_are_widened() {
+        assert_eq!(Some(DataType::Int32), unsigned_type(&DataType::Int16));
+        assert_eq!(Some(DataType::Int64), unsigned_type(&DataType::Int32));
+        assert_eq!(
+            Some(DataType::Decimal128(20, 0)),
+            unsigned_type(&DataType::Int64)
+        );
+    }
+
+    #[test]
+    fn non_integers_are_unchanged() {
+        assert_eq!(None, unsigned_type(&DataType::Utf8));
+    }
+}
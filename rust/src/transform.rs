@@ -0,0 +1,779 @@
+//! Transformations applied to each batch after it has been fetched from the data source.
+
+use std::{collections::HashMap, iter, sync::Arc};
+
+use arrow_odbc::arrow::{
+    array::{
+        make_array, new_null_array, Array, ArrayData, ArrayRef, BinaryArray, BooleanArray,
+        BooleanBufferBuilder, FixedSizeBinaryArray, FixedSizeListArray, Float32Array, Float64Array,
+        Int64Array, LargeStringArray, MapArray, StringArray, StructArray,
+    },
+    buffer::Buffer,
+    compute::{can_cast_types, cast, filter, is_not_null, max, min},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use sha2::{Digest, Sha256};
+
+/// Transforms a fetched batch. It must produce batches matching the schema the transformation has
+/// been created with.
+pub type Transform = Box<dyn Fn(RecordBatch) -> Result<RecordBatch, ArrowError>>;
+
+/// Replaces the key column with a column of maps, each holding a single entry made up of the key
+/// and the value of the row. The value column is removed. Rows with a `NULL` key yield a `NULL`
+/// map. Returns the schema of the transformed batches along with the transformation.
+pub fn pair_as_map(
+    schema: &Schema,
+    key_index: usize,
+    value_index: usize,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    check_column_index(schema, key_index)?;
+    check_column_index(schema, value_index)?;
+    if key_index == value_index {
+        return Err(ArrowError::InvalidArgumentError(
+            "Key and value of a map must be different columns.".to_string(),
+        ));
+    }
+
+    let key = schema.field(key_index);
+    let value = schema.field(value_index);
+    let entries = Field::new(
+        "entries",
+        DataType::Struct(vec![
+            Field::new("keys", key.data_type().clone(), false),
+            Field::new("values", value.data_type().clone(), true),
+        ]),
+        false,
+    );
+    let map = Field::new(
+        key.name(),
+        DataType::Map(Box::new(entries.clone()), false),
+        true,
+    );
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| index != value_index)
+        .map(|(index, field)| {
+            if index == key_index {
+                map.clone()
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    let target = Arc::new(Schema::new(fields));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let map = map_column(batch.column(key_index), batch.column(value_index), &entries)?;
+        let columns = batch
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != value_index)
+            .map(|(index, column)| {
+                if index == key_index {
+                    map.clone()
+                } else {
+                    column.clone()
+                }
+            })
+            .collect();
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    Ok((target, Box::new(transform)))
+}
+
+/// Emits the columns at the (zero based) `indices` in the order specified. Columns may be omitted
+/// or repeated. Returns the schema of the transformed batches along with the transformation.
+pub fn reorder(
+    schema: &Schema,
+    indices: Vec<usize>,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    for &index in &indices {
+        check_column_index(schema, index)?;
+    }
+    let target = Arc::new(schema.project(&indices)?);
+    let transform = move |batch: RecordBatch| batch.project(&indices);
+    Ok((target, Box::new(transform)))
+}
+
+/// Renames the columns. The first `names.len()` columns are named after the respective entry,
+/// `None` keeps the name of the column. Returns the schema of the transformed batches along with
+/// the transformation.
+pub fn rename(
+    schema: &Schema,
+    names: Vec<Option<String>>,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    let num_columns = schema.fields().len();
+    if names.len() > num_columns {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "{} column names have been specified, but the result set has only {num_columns} \
+            columns.",
+            names.len()
+        )));
+    }
+    let mut fields = schema.fields().clone();
+    for (field, name) in fields.iter_mut().zip(names) {
+        if let Some(name) = name {
+            *field = Field::new(&name, field.data_type().clone(), field.is_nullable())
+                .with_metadata(field.metadata().clone());
+        }
+    }
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform =
+        move |batch: RecordBatch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec());
+    Ok((target, Box::new(transform)))
+}
+
+/// Parses the text of the column at `column_index` as PostgreSQL array literals (e.g. `{1,2,NULL}`)
+/// into lists. The elements are cast from text to `element_type`, or stay strings if it is `None`.
+/// Elements which can not be cast become `NULL`. Only one dimensional arrays are supported. With
+/// `large` the lists and their strings use 64 Bit offsets, so batches may hold more than `i32::MAX`
+/// elements or bytes of text. Returns the schema of the transformed batches along with the
+/// transformation.
+pub fn parse_pg_arrays(
+    schema: &Schema,
+    column_index: usize,
+    element_type: Option<DataType>,
+    large: bool,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    check_column_index(schema, column_index)?;
+    let field = schema.field(column_index);
+    if field.data_type() != &DataType::Utf8 {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Column '{}' must be text to be parsed as array, but is {}.",
+            field.name(),
+            field.data_type()
+        )));
+    }
+    let text_type = if large {
+        DataType::LargeUtf8
+    } else {
+        DataType::Utf8
+    };
+    let element_type = element_type.unwrap_or_else(|| text_type.clone());
+    if !can_cast_types(&text_type, &element_type) {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Elements of the arrays in column '{}' can not be cast from text to {}.",
+            field.name(),
+            element_type
+        )));
+    }
+    let item = Box::new(Field::new("item", element_type, true));
+    let list_type = if large {
+        DataType::LargeList(item)
+    } else {
+        DataType::List(item)
+    };
+    let list = Field::new(field.name(), list_type.clone(), field.is_nullable());
+    let mut fields = schema.fields().clone();
+    fields[column_index] = list;
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let text = batch
+            .column(column_index)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let list = list_column(text, &list_type)?;
+        let mut columns = batch.columns().to_vec();
+        columns[column_index] = list;
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    Ok((target, Box::new(transform)))
+}
+
+fn list_column(text: &StringArray, list_type: &DataType) -> Result<ArrayRef, ArrowError> {
+    let mut elements = Vec::new();
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut validity = BooleanBufferBuilder::new(text.len());
+    offsets.push(0usize);
+    for literal in text.iter() {
+        if let Some(literal) = literal {
+            elements.extend(parse_pg_array(literal)?);
+        }
+        offsets.push(elements.len());
+        validity.append(literal.is_some());
+    }
+
+    let (offsets, elements, item) = if let DataType::LargeList(item) = list_type {
+        let offsets: Vec<i64> = offsets.iter().map(|&offset| offset as i64).collect();
+        let elements: ArrayRef = Arc::new(LargeStringArray::from(elements));
+        (Buffer::from_slice_ref(&offsets), elements, item)
+    } else if let DataType::List(item) = list_type {
+        let overflow = || {
+            ArrowError::InvalidArgumentError(
+                "Parsed arrays exceed the 32 Bit offsets of a list. Use large lists instead."
+                    .to_string(),
+            )
+        };
+        let offsets = offsets
+            .iter()
+            .map(|&offset| i32::try_from(offset).map_err(|_| overflow()))
+            .collect::<Result<Vec<i32>, _>>()?;
+        // Also the offsets of the strings must fit into 32 Bit.
+        let text_len: usize = elements.iter().flatten().map(String::len).sum();
+        if i32::try_from(text_len).is_err() {
+            return Err(overflow());
+        }
+        let elements: ArrayRef = Arc::new(StringArray::from(elements));
+        (Buffer::from_slice_ref(&offsets), elements, item)
+    } else {
+        unreachable!("Parsed arrays are always emitted as lists")
+    };
+    let elements = cast(&elements, item.data_type())?;
+    let data = ArrayData::builder(list_type.clone())
+        .len(text.len())
+        .add_buffer(offsets)
+        .add_child_data(elements.data().clone())
+        .null_bit_buffer(Some(validity.finish()))
+        .build()?;
+    Ok(make_array(data))
+}
+
+/// Splits a PostgreSQL array literal like `{a,"b c",NULL}` into its elements. Quoted elements may
+/// contain backslash escapes. An unquoted `NULL` is a `NULL` element.
+fn parse_pg_array(literal: &str) -> Result<Vec<Option<String>>, ArrowError> {
+    let invalid = |reason: &str| {
+        ArrowError::ParseError(format!("Invalid array literal '{literal}': {reason}"))
+    };
+    let inner = literal
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or_else(|| invalid("must be enclosed in braces"))?;
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Ok(elements);
+    }
+
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let element = match chars.peek() {
+            Some('{') => return Err(invalid("nested arrays are not supported")),
+            Some('"') => {
+                chars.next();
+                let mut element = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => element.extend(chars.next()),
+                        Some(c) => element.push(c),
+                        None => return Err(invalid("unterminated quote")),
+                    }
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                Some(element)
+            }
+            _ => {
+                let mut element = String::new();
+                while let Some(c) = chars.next_if(|&c| c != ',') {
+                    element.push(c);
+                }
+                let element = element.trim();
+                if element.eq_ignore_ascii_case("NULL") {
+                    None
+                } else {
+                    Some(element.to_string())
+                }
+            }
+        };
+        elements.push(element);
+        match chars.next() {
+            Some(',') => continue,
+            None => break,
+            Some(_) => return Err(invalid("expected ',' after quoted element")),
+        }
+    }
+    Ok(elements)
+}
+
+/// Maps the single character text of the column at `column_index` to booleans, e.g. `'Y'` and
+/// `'N'` flags of legacy schemas. Trailing spaces are ignored. Values other than `true_char` or
+/// `false_char` become `NULL`. Returns the schema of the transformed batches along with the
+/// transformation.
+pub fn char_as_bool(
+    schema: &Schema,
+    column_index: usize,
+    true_char: char,
+    false_char: char,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    check_column_index(schema, column_index)?;
+    let field = schema.field(column_index);
+    if field.data_type() != &DataType::Utf8 {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Column '{}' must be text to be read as boolean, but is {}.",
+            field.name(),
+            field.data_type()
+        )));
+    }
+    let mut fields = schema.fields().clone();
+    // Unknown characters become `NULL`, so the column is nullable even if the source is not.
+    fields[column_index] = Field::new(field.name(), DataType::Boolean, true);
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let text = batch
+            .column(column_index)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let flags: BooleanArray = text
+            .iter()
+            .map(|text| {
+                let mut chars = text?.trim_end_matches(' ').chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c == true_char => Some(true),
+                    (Some(c), None) if c == false_char => Some(false),
+                    _ => None,
+                }
+            })
+            .collect();
+        let mut columns = batch.columns().to_vec();
+        columns[column_index] = Arc::new(flags);
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    Ok((target, Box::new(transform)))
+}
+
+/// How [`mask`] hides the values of a column.
+pub enum Mask {
+    /// Replaces each value with the hex encoded SHA-256 hash of its bytes. Text is hashed as utf-8,
+    /// other types are formatted as text first. Equal values yield equal hashes, so the column may
+    /// still be used to join or count distinct values.
+    Sha256,
+    /// Replaces all values with `NULL`.
+    Redact,
+}
+
+/// Hides the values of the column at `column_index`, so they never leave the native code. Returns
+/// the schema of the transformed batches along with the transformation.
+pub fn mask(
+    schema: &Schema,
+    column_index: usize,
+    mask: Mask,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    check_column_index(schema, column_index)?;
+    let field = schema.field(column_index);
+    let data_type = match mask {
+        Mask::Sha256 => DataType::Utf8,
+        Mask::Redact => field.data_type().clone(),
+    };
+    let nullable = match mask {
+        Mask::Sha256 => field.is_nullable(),
+        Mask::Redact => true,
+    };
+    let mut fields = schema.fields().clone();
+    fields[column_index] = Field::new(field.name(), data_type, nullable);
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let column = batch.column(column_index);
+        let masked = match mask {
+            Mask::Sha256 => sha256_column(column)?,
+            Mask::Redact => new_null_array(column.data_type(), column.len()),
+        };
+        let mut columns = batch.columns().to_vec();
+        columns[column_index] = masked;
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    Ok((target, Box::new(transform)))
+}
+
+fn sha256_column(column: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let hex = |bytes: &[u8]| format!("{:x}", Sha256::digest(bytes));
+    let hashes: StringArray = if let Some(binary) = column.as_any().downcast_ref::<BinaryArray>() {
+        binary.iter().map(|value| value.map(hex)).collect()
+    } else {
+        let text = cast(column, &DataType::Utf8)?;
+        let text = text.as_any().downcast_ref::<StringArray>().unwrap();
+        text.iter()
+            .map(|value| value.map(|value| hex(value.as_bytes())))
+            .collect()
+    };
+    Ok(Arc::new(hashes))
+}
+
+/// Replaces empty strings in all text columns with `NULL`. Some data sources, like Oracle, do not
+/// distinguish between the two, so this allows for reading the same data consistently across
+/// sources. Returns the schema of the transformed batches along with the transformation.
+pub fn empty_strings_as_null(schema: &Schema) -> (SchemaRef, Transform) {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Utf8 => Field::new(field.name(), DataType::Utf8, true),
+            _ => field.clone(),
+        })
+        .collect();
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| match column.as_any().downcast_ref::<StringArray>() {
+                Some(text) => {
+                    let text: StringArray = text
+                        .iter()
+                        .map(|value| value.filter(|value| !value.is_empty()))
+                        .collect();
+                    Arc::new(text) as ArrayRef
+                }
+                None => column.clone(),
+            })
+            .collect();
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    (target, Box::new(transform))
+}
+
+/// Attaches `metadata` to the schema. Existing entries with the same keys are replaced. Returns the
+/// schema of the transformed batches along with the transformation.
+pub fn schema_metadata(
+    schema: &Schema,
+    metadata: HashMap<String, String>,
+) -> (SchemaRef, Transform) {
+    let mut merged = schema.metadata().clone();
+    merged.extend(metadata);
+    let target = Arc::new(Schema::new_with_metadata(schema.fields().clone(), merged));
+
+    let schema = target.clone();
+    let transform =
+        move |batch: RecordBatch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec());
+    (target, Box::new(transform))
+}
+
+/// Casts each `Int64` column of the batch to the narrowest signed integer type able to hold all of
+/// its values. In contrast to the transformations, the resulting schema depends on the values of
+/// the batch, so it may differ between batches. Columns holding only `NULL`s are left as they are.
+pub fn downcast_integers(batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let narrow = column
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .and_then(|integers| Some((min(integers)?, max(integers)?)))
+            .and_then(|(lowest, highest)| narrowest_integer(lowest, highest));
+        if let Some(data_type) = narrow {
+            fields.push(Field::new(field.name(), data_type.clone(), field.is_nullable()));
+            columns.push(cast(column, &data_type)?);
+        } else {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+/// Narrowest signed integer type narrower than `Int64`, which holds all values between `lowest`
+/// and `highest`.
+fn narrowest_integer(lowest: i64, highest: i64) -> Option<DataType> {
+    let fits = |lower: i64, upper: i64| lower <= lowest && highest <= upper;
+    if fits(i8::MIN.into(), i8::MAX.into()) {
+        Some(DataType::Int8)
+    } else if fits(i16::MIN.into(), i16::MAX.into()) {
+        Some(DataType::Int16)
+    } else if fits(i32::MIN.into(), i32::MAX.into()) {
+        Some(DataType::Int32)
+    } else {
+        None
+    }
+}
+
+fn map_column(keys: &ArrayRef, values: &ArrayRef, entries: &Field) -> Result<ArrayRef, ArrowError> {
+    // Map keys must not be `NULL`, so these rows do not contribute an entry.
+    let present = is_not_null(keys.as_ref())?;
+    let entry_fields = match entries.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        _ => unreachable!("Entries of a map are always a struct"),
+    };
+    let entries_array = StructArray::from(vec![
+        (entry_fields[0].clone(), filter(keys.as_ref(), &present)?),
+        (entry_fields[1].clone(), filter(values.as_ref(), &present)?),
+    ]);
+
+    let mut offsets = Vec::with_capacity(present.len() + 1);
+    let mut validity = BooleanBufferBuilder::new(present.len());
+    let mut offset = 0i32;
+    offsets.push(offset);
+    for is_present in present.iter() {
+        let is_present = is_present.unwrap_or(false);
+        if is_present {
+            offset += 1;
+        }
+        offsets.push(offset);
+        validity.append(is_present);
+    }
+
+    let data = ArrayData::builder(DataType::Map(Box::new(entries.clone()), false))
+        .len(present.len())
+        .add_buffer(Buffer::from_slice_ref(&offsets))
+        .add_child_data(entries_array.data().clone())
+        .null_bit_buffer(Some(validity.finish()))
+        .build()?;
+    Ok(Arc::new(MapArray::from(data)))
+}
+
+/// Reinterprets the column at `column_index` as fixed size lists of `size` floating point numbers,
+/// e.g. embeddings. Text is parsed as comma separated numbers, optionally enclosed in brackets or
+/// braces (e.g. `[0.5,1,2]`). Binary is read as `size` consecutive little endian floats. Returns
+/// the schema of the transformed batches along with the transformation.
+pub fn fixed_size_list(
+    schema: &Schema,
+    column_index: usize,
+    element_type: DataType,
+    size: usize,
+) -> Result<(SchemaRef, Transform), ArrowError> {
+    check_column_index(schema, column_index)?;
+    if !matches!(element_type, DataType::Float32 | DataType::Float64) {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Elements of fixed size lists must be Float32 or Float64, not {element_type}."
+        )));
+    }
+    let field = schema.field(column_index);
+    if !matches!(
+        field.data_type(),
+        DataType::Utf8 | DataType::Binary | DataType::FixedSizeBinary(_)
+    ) {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Column '{}' must be text or binary to be read as fixed size list, but is {}.",
+            field.name(),
+            field.data_type()
+        )));
+    }
+    let item = Field::new("item", element_type.clone(), true);
+    let list_type = DataType::FixedSizeList(Box::new(item), size as i32);
+    let mut fields = schema.fields().clone();
+    fields[column_index] = Field::new(field.name(), list_type.clone(), field.is_nullable());
+    let target = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let schema = target.clone();
+    let transform = move |batch: RecordBatch| {
+        let column = batch.column(column_index);
+        let rows = match column.data_type() {
+            DataType::Utf8 => column
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap()
+                .iter()
+                .map(|text| text.map(|text| parse_floats(text, size)).transpose())
+                .collect::<Result<Vec<_>, _>>()?,
+            DataType::Binary => {
+                let binary = column.as_any().downcast_ref::<BinaryArray>().unwrap();
+                decode_rows(binary, |index| binary.value(index), &element_type, size)?
+            }
+            _ => {
+                let binary = column
+                    .as_any()
+                    .downcast_ref::<FixedSizeBinaryArray>()
+                    .unwrap();
+                decode_rows(binary, |index| binary.value(index), &element_type, size)?
+            }
+        };
+        let mut columns = batch.columns().to_vec();
+        columns[column_index] = fixed_size_list_column(&rows, &list_type, &element_type, size)?;
+        RecordBatch::try_new(schema.clone(), columns)
+    };
+    Ok((target, Box::new(transform)))
+}
+
+fn parse_floats(text: &str, size: usize) -> Result<Vec<f64>, ArrowError> {
+    let inner = text
+        .trim()
+        .trim_start_matches(|c| matches!(c, '[' | '{' | '('))
+        .trim_end_matches(|c| matches!(c, ']' | '}' | ')'));
+    let values = if inner.trim().is_empty() {
+        Vec::new()
+    } else {
+        inner
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ArrowError::ParseError(format!("Invalid number in '{text}': {e}")))?
+    };
+    if values.len() != size {
+        return Err(ArrowError::ParseError(format!(
+            "Expected {size} elements, but '{text}' has {}.",
+            values.len()
+        )));
+    }
+    Ok(values)
+}
+
+fn decode_rows<'a>(
+    array: &dyn Array,
+    value: impl Fn(usize) -> &'a [u8],
+    element_type: &DataType,
+    size: usize,
+) -> Result<Vec<Option<Vec<f64>>>, ArrowError> {
+    (0..array.len())
+        .map(|index| {
+            if array.is_null(index) {
+                return Ok(None);
+            }
+            let bytes = value(index);
+            let width = if element_type == &DataType::Float32 { 4 } else { 8 };
+            if bytes.len() != width * size {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "Expected {} bytes for {size} elements of type {element_type}, but got {}.",
+                    width * size,
+                    bytes.len()
+                )));
+            }
+            let values = bytes
+                .chunks_exact(width)
+                .map(|chunk| {
+                    if width == 4 {
+                        f32::from_le_bytes(chunk.try_into().unwrap()) as f64
+                    } else {
+                        f64::from_le_bytes(chunk.try_into().unwrap())
+                    }
+                })
+                .collect();
+            Ok(Some(values))
+        })
+        .collect()
+}
+
+fn fixed_size_list_column(
+    rows: &[Option<Vec<f64>>],
+    list_type: &DataType,
+    element_type: &DataType,
+    size: usize,
+) -> Result<ArrayRef, ArrowError> {
+    let mut values = Vec::with_capacity(rows.len() * size);
+    let mut validity = BooleanBufferBuilder::new(rows.len());
+    for row in rows {
+        match row {
+            Some(row) => values.extend_from_slice(row),
+            // Null lists still occupy their slots in the child array.
+            None => values.extend(iter::repeat(0.0).take(size)),
+        }
+        validity.append(row.is_some());
+    }
+    let values: ArrayRef = if element_type == &DataType::Float32 {
+        Arc::new(Float32Array::from(
+            values.iter().map(|&v| v as f32).collect::<Vec<_>>(),
+        ))
+    } else {
+        Arc::new(Float64Array::from(values))
+    };
+
+    let data = ArrayData::builder(list_type.clone())
+        .len(rows.len())
+        .add_child_data(values.data().clone())
+        .null_bit_buffer(Some(validity.finish()))
+        .build()?;
+    Ok(Arc::new(FixedSizeListArray::from(data)))
+}
+
+fn check_column_index(schema: &Schema, column_index: usize) -> Result<(), ArrowError> {
+    let num_columns = schema.fields().len();
+    if column_index >= num_columns {
+        Err(ArrowError::InvalidArgumentError(format!(
+            "Column index {column_index} is out of range. The result set has only {num_columns} \
+            columns."
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_odbc::arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+
+    use super::{downcast_integers, narrowest_integer, parse_pg_array};
+
+    fn elements(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|value| value.map(str::to_owned)).collect()
+    }
+
+    #[test]
+    fn empty_pg_array() {
+        assert_eq!(elements(&[]), parse_pg_array("{}").unwrap());
+    }
+
+    #[test]
+    fn pg_array_with_null_element() {
+        assert_eq!(elements(&[None]), parse_pg_array("{NULL}").unwrap());
+    }
+
+    #[test]
+    fn quoted_pg_array_elements() {
+        let actual = parse_pg_array(r#"{"a,b","NULL","say \"hi\"",c}"#).unwrap();
+
+        assert_eq!(
+            elements(&[Some("a,b"), Some("NULL"), Some("say \"hi\""), Some("c")]),
+            actual
+        );
+    }
+
+    #[test]
+    fn nested_pg_arrays_are_rejected() {
+        assert!(parse_pg_array("{{1,2},{3,4}}").is_err());
+    }
+
+    #[test]
+    fn unterminated_quote_in_pg_array() {
+        assert!(parse_pg_array(r#"{"a}"#).is_err());
+    }
+
+    #[test]
+    fn narrowest_integer_at_boundaries() {
+        let narrowest = |lowest: i64, highest: i64| narrowest_integer(lowest, highest);
+
+        assert_eq!(Some(DataType::Int8), narrowest(i8::MIN.into(), i8::MAX.into()));
+        assert_eq!(Some(DataType::Int16), narrowest(i8::MIN.into(), i8::MAX as i64 + 1));
+        assert_eq!(Some(DataType::Int16), narrowest(i16::MIN.into(), i16::MAX.into()));
+        assert_eq!(Some(DataType::Int32), narrowest(i16::MIN as i64 - 1, 0));
+        assert_eq!(Some(DataType::Int32), narrowest(i32::MIN.into(), i32::MAX.into()));
+        assert_eq!(None, narrowest(0, i32::MAX as i64 + 1));
+        assert_eq!(None, narrowest(i64::MIN, i64::MAX));
+    }
+
+    #[test]
+    fn downcast_integers_leaves_null_columns_alone() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let column = Int64Array::from(vec![None, None]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(column)]).unwrap();
+
+        let actual = downcast_integers(batch).unwrap();
+
+        assert_eq!(&DataType::Int64, actual.schema().field(0).data_type());
+    }
+
+    #[test]
+    fn downcast_integers_to_int8() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let column = Int64Array::from(vec![Some(-128), None, Some(127)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(column)]).unwrap();
+
+        let actual = downcast_integers(batch).unwrap();
+
+        assert_eq!(&DataType::Int8, actual.schema().field(0).data_type());
+        assert_eq!(1, actual.column(0).null_count());
+    }
+}
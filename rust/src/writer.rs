@@ -2,24 +2,30 @@ use std::{
     ffi::c_void,
     ptr::{null_mut, NonNull},
     slice, str,
+    sync::Arc,
 };
 
 use arrow_odbc::{
     arrow::{
-        array::StructArray,
-        datatypes::Schema,
+        array::{new_null_array, Array, StructArray},
+        compute::cast,
+        datatypes::{DataType, Field, Schema},
+        error::ArrowError,
         ffi::{ArrowArray, ArrowArrayRef, FFI_ArrowArray, FFI_ArrowSchema},
         record_batch::RecordBatch,
     },
-    odbc_api::StatementConnection,
+    odbc_api::{Environment, StatementConnection},
     OdbcWriter,
 };
 
 use crate::{try_, ArrowOdbcError, OdbcConnection};
 
 /// Opaque type holding all the state associated with an ODBC writer implementation in Rust. This
-/// type also has ownership of the ODBC Connection handle.
-pub struct ArrowOdbcWriter(OdbcWriter<StatementConnection<'static>>);
+/// type also has ownership of the ODBC Connection handle and shares ownership of the environment it
+/// has been opened with.
+// SAFETY: The statement connection outlives its borrow of the environment only as long as the
+// `Arc<Environment>` is declared after it. Do not reorder the fields.
+pub struct ArrowOdbcWriter(OdbcWriter<StatementConnection<'static>>, Arc<Environment>);
 
 /// Frees the resources associated with an ArrowOdbcWriter
 ///
@@ -42,6 +48,10 @@ pub unsafe extern "C" fn arrow_odbc_writer_free(writer: NonNull<ArrowOdbcWriter>
 ///   afterwards.
 /// * `table_buf` must point to a valid utf-8 string
 /// * `table_len` describes the len of `table_buf` in bytes.
+/// * `schema_name_buf` may be `NULL` in which case the table is not qualified with a schema.
+///   Otherwise it must point to a valid utf-8 string and the table is qualified with it. Schema and
+///   table name are used verbatim in the insert statement, so they may already be quoted.
+/// * `schema_name_len` describes the len of `schema_name_buf` in bytes.
 /// * `schema` pointer to an arrow schema.
 /// * `writer_out` in case of success this will point to an instance of `ArrowOdbcWriter`. Ownership
 ///   is transferred to the caller.
@@ -50,23 +60,74 @@ pub unsafe extern "C" fn arrow_odbc_writer_make(
     connection: NonNull<OdbcConnection>,
     table_buf: *const u8,
     table_len: usize,
+    schema_name_buf: *const u8,
+    schema_name_len: usize,
     chunk_size: usize,
     schema: *const c_void,
     writer_out: *mut *mut ArrowOdbcWriter,
 ) -> *mut ArrowOdbcError {
     let connection = *Box::from_raw(connection.as_ptr());
-    let connection = connection.0;
 
     let table = slice::from_raw_parts(table_buf, table_len);
     let table = str::from_utf8(table).unwrap();
+    let table = if schema_name_buf.is_null() {
+        table.to_owned()
+    } else {
+        let schema_name = slice::from_raw_parts(schema_name_buf, schema_name_len);
+        let schema_name = str::from_utf8(schema_name).unwrap();
+        format!("{}.{}", schema_name, table)
+    };
 
     let schema = schema as *const FFI_ArrowSchema;
     let schema: Schema = try_!((&*schema).try_into());
+    let schema = decoded_schema(&schema);
 
     let writer = try_!(OdbcWriter::from_connection(
-        connection, &schema, table, chunk_size
+        connection.0, &schema, &table, chunk_size
     ));
-    *writer_out = Box::into_raw(Box::new(ArrowOdbcWriter(writer)));
+    *writer_out = Box::into_raw(Box::new(ArrowOdbcWriter(writer, connection.1)));
+
+    null_mut() // Ok(())
+}
+
+/// Creates an Arrow ODBC writer instance executing an arbitrary parameterized statement, rather
+/// than a generated `INSERT`. The columns of each written batch are bound positionally to the
+/// placeholders (`?`) of `sql_buf`. The statement is executed once for every row, using arrays of
+/// parameters to send up to `chunk_size` rows in one roundtrip.
+///
+/// Takes ownership of connection even in case of an error.
+///
+/// # Safety
+///
+/// * `connection` must point to a valid OdbcConnection. This function takes ownership of the
+///   connection, even in case of an error. So The connection must not be freed explicitly
+///   afterwards.
+/// * `sql_buf` must point to a valid utf-8 string
+/// * `sql_len` describes the len of `sql_buf` in bytes.
+/// * `schema` pointer to an arrow schema.
+/// * `writer_out` in case of success this will point to an instance of `ArrowOdbcWriter`. Ownership
+///   is transferred to the caller.
+#[no_mangle]
+pub unsafe extern "C" fn arrow_odbc_writer_prepare(
+    connection: NonNull<OdbcConnection>,
+    sql_buf: *const u8,
+    sql_len: usize,
+    chunk_size: usize,
+    schema: *const c_void,
+    writer_out: *mut *mut ArrowOdbcWriter,
+) -> *mut ArrowOdbcError {
+    let connection = *Box::from_raw(connection.as_ptr());
+
+    let sql = slice::from_raw_parts(sql_buf, sql_len);
+    let sql = str::from_utf8(sql).unwrap();
+
+    let schema = schema as *const FFI_ArrowSchema;
+    let schema: Schema = try_!((&*schema).try_into());
+    let schema = decoded_schema(&schema);
+
+    let prepared = try_!(connection.0.into_prepared(sql));
+    let writer = try_!(OdbcWriter::new(chunk_size, &schema, prepared));
+    *writer_out = Box::into_raw(Box::new(ArrowOdbcWriter(writer, connection.1)));
 
     null_mut() // Ok(())
 }
@@ -88,6 +149,7 @@ pub unsafe extern "C" fn arrow_odbc_writer_write_batch(
     let array_data = try_!(arrow_array.to_data());
     let struct_array = StructArray::from(array_data);
     let record_batch = RecordBatch::from(&struct_array);
+    let record_batch = try_!(decode_columns(record_batch));
 
     // Dereference writer
     let writer = &mut writer.as_mut().0;
@@ -109,3 +171,44 @@ pub unsafe extern "C" fn arrow_odbc_writer_flush(
     try_!(writer.flush());
     null_mut()
 }
+
+/// Dictionary encoded fields are replaced with fields of their value type. Dictionaries are
+/// decoded before binding, so e.g. a categorical column can be inserted into a `VARCHAR` column.
+/// Fields of type `Null` can not be bound either. They are replaced with nullable text fields, so
+/// their values are inserted as `NULL`.
+fn decoded_schema(schema: &Schema) -> Schema {
+    let fields = schema.fields().iter().map(decoded_field).collect();
+    Schema::new(fields)
+}
+
+fn decoded_field(field: &Field) -> Field {
+    match field.data_type() {
+        DataType::Dictionary(_, value_type) => {
+            Field::new(field.name(), value_type.as_ref().clone(), field.is_nullable())
+        }
+        DataType::Null => Field::new(field.name(), DataType::Utf8, true),
+        _ => field.clone(),
+    }
+}
+
+/// Materializes the values of all dictionary encoded columns in the batch, and replaces columns of
+/// type `Null` with text columns holding only `NULL`s.
+fn decode_columns(batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let needs_decoding =
+        |field: &Field| matches!(field.data_type(), DataType::Dictionary(_, _) | DataType::Null);
+    if !schema.fields().iter().any(needs_decoding) {
+        return Ok(batch);
+    }
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| match field.data_type() {
+            DataType::Dictionary(_, value_type) => cast(column, value_type),
+            DataType::Null => Ok(new_null_array(&DataType::Utf8, column.len())),
+            _ => Ok(column.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    RecordBatch::try_new(Arc::new(decoded_schema(&schema)), columns)
+}
@@ -1,13 +1,37 @@
 import os
+import time
 
 import pyarrow as pa
 import pyarrow.csv as csv
+import pyarrow.parquet as pq
 
+from datetime import date
+from decimal import Decimal
 from subprocess import run, check_output
 
 from pytest import raises
 
-from arrow_odbc import read_arrow_batches_from_odbc, Error
+from arrow_odbc import (
+    read_arrow_batches_from_odbc,
+    Environment,
+    Error,
+    ConstraintViolation,
+    SyntaxOrAccessError,
+    enable_odbc_connection_pooling,
+    disable_odbc_connection_pooling,
+    set_odbc_connection_pool_matching,
+    execute,
+    execute_many,
+    DEFAULT,
+    TypedNull,
+    max_column_name_len,
+    column_defaults,
+    prepare,
+    validate_connection_string,
+    Transport,
+    transport_attributes,
+    build_connection_string,
+)
 from arrow_odbc.writer import insert_into_table
 
 MSSQL = "Driver={ODBC Driver 17 for SQL Server};Server=localhost;UID=SA;PWD=My@Test@Password1;"
@@ -79,6 +103,25 @@ def test_empty_table():
         next(iter(reader))
 
 
+def test_empty_table_into_pyarrow_record_batch_reader():
+    """
+    An empty result set should still be exported as a stream carrying the schema.
+    """
+    table = "EmptyStream"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+
+    query = f"SELECT * FROM {table}"
+
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=100, connection_string=MSSQL
+    )
+    actual = reader.into_pyarrow_record_batch_reader().read_all()
+
+    assert pa.schema([("a", pa.int32())]) == actual.schema
+    assert 0 == actual.num_rows
+
+
 def test_one_row():
     """
     Query a table with one row. Should return one batch
@@ -185,6 +228,32 @@ def test_specify_user_and_password_separatly():
         next(it)
 
 
+def test_user_and_password_with_non_ascii_characters():
+    """
+    Credentials are passed with their length in bytes, so non-ASCII characters do not truncate
+    them.
+    """
+    # Given
+    setup = (
+        "IF SUSER_ID('Müller') IS NULL "
+        "CREATE LOGIN [Müller] WITH PASSWORD = N'Pässwörd@1234', CHECK_POLICY = OFF;"
+    )
+    execute(query=setup, connection_string=MSSQL)
+    connection_string = "Driver={ODBC Driver 17 for SQL Server};Server=localhost;"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT SUSER_NAME() AS name",
+        batch_size=1,
+        connection_string=connection_string,
+        user="Müller",
+        password="Pässwörd@1234",
+    )
+
+    # Then
+    assert ["Müller"] == next(iter(reader)).column(0).to_pylist()
+
+
 def test_query_char():
     """
     Query a string those UTF-16 representation is larger than the maximum binary column length on
@@ -418,55 +487,1477 @@ def test_support_varbinary_max():
         next(it)
 
 
-def test_insert_should_raise_on_invalid_connection_string():
+def test_spill_to_dir(tmp_path):
     """
-    Insert should raise on invalid connection string
+    Spill batches to Arrow IPC files and replay them afterwards.
     """
     # Given
-    invalid_connection_string = "FOO"
-    schema = pa.schema([("a", pa.int64())])
+    table = "SpillToDir"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+    rows = "a\n1\n2\n3\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
 
-    def iter_record_batches():
-        for i in range(2):
-            yield pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+    query = f"SELECT a FROM {table}"
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=2, connection_string=MSSQL
+    )
+
+    # When
+    spilled = reader.spill_to_dir(str(tmp_path))
+
+    # Then
+    assert 2 == len(list(tmp_path.iterdir()))
+    actual = spilled.read_all().to_pydict()
+    assert {"a": [1, 2, 3]} == actual
+
+
+def test_toggle_connection_pooling():
+    """
+    Switching connection pooling on and off again must not break subsequent queries.
+    """
+    enable_odbc_connection_pooling()
+    disable_odbc_connection_pooling()
+
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 42 as a", batch_size=100, connection_string=MSSQL
+    )
+    actual = next(iter(reader)).to_pydict()
+
+    assert {"a": [42]} == actual
 
-    reader = pa.RecordBatchReader.from_batches(schema, iter_record_batches())
+
+def test_set_connection_pool_matching_after_connecting():
+    """
+    Pool matching is applied to the ODBC environment once allocated, so changing it after the first
+    connection has been opened should raise.
+    """
+    # Given a connection has been opened in this process
+    execute(query="SELECT 1", connection_string=MSSQL)
 
     # When / Then
-    with raises(Error, match="Data source name not found"):
-        insert_into_table(
-            connection_string=invalid_connection_string,
-            chunk_size=20,
-            table="MyTable",
-            reader=reader,
-        )
+    with raises(Error, match="before opening the first connection"):
+        set_odbc_connection_pool_matching(strict=False)
 
 
-def test_insert_batches():
+def test_rowversion():
     """
-    Insert data into database
+    SQL Server's rowversion is reported as BINARY(8) and should therefore be read as a fixed size
+    binary of eight bytes.
     """
-    # Given
-    table = "InsertBatches"
+    table = "RowVersion"
     os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
     os.system(
-        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int IDENTITY(1,1), a BIGINT)"'
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int, b rowversion);"'
     )
-    schema = pa.schema([("a", pa.int64())])
+    rows = "a\n42\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
 
-    def iter_record_batches():
-        for i in range(2):
-            yield pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+    query = f"SELECT b FROM {table}"
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=100, connection_string=MSSQL
+    )
+    batch = next(iter(reader))
 
-    reader = pa.RecordBatchReader.from_batches(schema, iter_record_batches())
+    assert pa.schema([("b", pa.binary(8))]) == reader.schema
+    assert 8 == len(batch.column(0)[0].as_py())
+
+
+def test_execute_update():
+    """
+    Execute a statement without result set and report the number of affected rows.
+    """
+    # Given
+    table = "ExecuteUpdate"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+    rows = "a\n1\n2\n3\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
 
     # When
-    insert_into_table(
-        connection_string=MSSQL, chunk_size=20, table=table, reader=reader
+    rows_affected = execute(
+        query=f"UPDATE {table} SET a = 42 WHERE a > ?",
+        connection_string=MSSQL,
+        parameters=["1"],
     )
 
     # Then
+    assert 2 == rows_affected
     actual = check_output(
-        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY a"]
     )
-    assert "a\n1\n2\n3\n1\n2\n3\n" == actual.decode("utf8")
+    assert "a\n1\n42\n42\n" == actual.decode("utf8")
+
+
+def test_drain_with_callback():
+    """
+    Drain a reader, observing only the number of rows in each batch.
+    """
+    # Given
+    table = "DrainWithCallback"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+    rows = "a\n1\n2\n3\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT a FROM {table}", batch_size=2, connection_string=MSSQL
+    )
+
+    # When
+    rows_per_batch = []
+    reader.drain(rows_per_batch.append)
+
+    # Then
+    assert [2, 1] == rows_per_batch
+
+
+def test_unsigned_tinyint():
+    """
+    SQL Server's TINYINT is unsigned. Values above 127 must not wrap around.
+    """
+    table = "UnsignedTinyInt"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a TINYINT);"')
+    rows = "a\n200\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT a FROM {table}", batch_size=100, connection_string=MSSQL
+    )
+    actual = next(iter(reader))
+
+    schema = pa.schema([("a", pa.uint8())])
+    expected = pa.RecordBatch.from_pydict({"a": [200]}, schema)
+    assert expected == actual
+
+
+def test_read_all():
+    """
+    Fetch the entire result set into a single table.
+    """
+    table = "ReadAll"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+    rows = "a\n1\n2\n3\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT a FROM {table}", batch_size=2, connection_string=MSSQL
+    )
+
+    actual = reader.read_all()
+
+    assert {"a": [1, 2, 3]} == actual.to_pydict()
+
+
+def test_default_parameter():
+    """
+    Let a column fall back to its default value in a parameterized insert.
+    """
+    # Given
+    table = "DefaultParameter"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a VARCHAR(10), b int DEFAULT 42);"'
+    )
+
+    # When
+    read_arrow_batches_from_odbc(
+        query=f"INSERT INTO {table} (a, b) VALUES (?, ?)",
+        batch_size=100,
+        connection_string=MSSQL,
+        parameters=["Hello", DEFAULT],
+    )
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a, b FROM {table}"]
+    )
+    assert "a,b\nHello,42\n" == actual.decode("utf8")
+
+
+def test_partition_by():
+    """
+    Each emitted batch should only contain a single value of the partition column.
+    """
+    # Given
+    table = "PartitionBy"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int, b int);"')
+    rows = "a,b\n1,1\n2,2\n1,3\n2,4\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT a, b FROM {table} ORDER BY b",
+        batch_size=100,
+        connection_string=MSSQL,
+        partition_by=0,
+    )
+    actual = [batch.to_pydict() for batch in reader]
+
+    # Then
+    assert [{"a": [1, 1], "b": [1, 3]}, {"a": [2, 2], "b": [2, 4]}] == actual
+
+
+def test_query_tag():
+    """
+    The query tag should be visible as part of the statement text to the database.
+    """
+    # Given
+    query = (
+        "SELECT CAST(t.text AS VARCHAR(1000)) AS a FROM sys.dm_exec_requests r "
+        "CROSS APPLY sys.dm_exec_sql_text(r.sql_handle) t WHERE r.session_id = @@SPID"
+    )
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query,
+        batch_size=1,
+        connection_string=MSSQL,
+        query_tag="nightly-export",
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert batch.to_pydict()["a"][0].startswith("/* nightly-export */ SELECT")
+
+
+def test_query_with_integer_parameter():
+    """
+    Integer parameters should be bound as ``BIGINT``, rather than as text.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a, ? AS b",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[42, 2**70],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.int64() == reader.schema.field("a").type
+    assert [42] == batch.column(0).to_pylist()
+    # Integers exceeding 64 Bit are passed as text
+    assert [str(2**70)] == batch.column(1).to_pylist()
+
+
+def test_query_with_float_parameter():
+    """
+    Float parameters should be bound as ``DOUBLE``, so no precision is lost to formatting.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a", batch_size=1, connection_string=MSSQL, parameters=[0.1]
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.float64() == reader.schema.field("a").type
+    assert [0.1] == batch.column(0).to_pylist()
+
+
+def test_query_with_date_parameter():
+    """
+    ``datetime.date`` parameters should be bound as ``DATE``, also for dates before 1900.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT CAST(? AS DATE) AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[date(1582, 10, 4)],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert [date(1582, 10, 4)] == batch.column(0).to_pylist()
+
+
+def test_query_with_bool_parameter():
+    """
+    Boolean parameters should be bound as ``BIT``, rather than as integers or text.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a, ? AS b",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[True, False],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.bool_() == reader.schema.field("a").type
+    assert {"a": [True], "b": [False]} == batch.to_pydict()
+
+
+def test_query_with_decimal_parameter():
+    """
+    ``decimal.Decimal`` parameters should be bound as ``NUMERIC``, so no digits are lost to floating
+    point.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[Decimal("-1234567890123456789.01")],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert [Decimal("-1234567890123456789.01")] == batch.column(0).to_pylist()
+
+
+def test_query_with_bytes_parameter():
+    """
+    ``bytes`` parameters should be bound as ``VARBINARY``. An empty value must not become ``NULL``.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a, ? AS b",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[b"\x00\xff", b""],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert {"a": [b"\x00\xff"], "b": [b""]} == batch.to_pydict()
+
+
+def test_query_with_memoryview_parameter():
+    """
+    A ``memoryview`` parameter should be bound as ``VARBINARY`` without copying its bytes.
+    """
+    # Given
+    payload = bytearray(b"\x00\x01\xff")
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[memoryview(payload)],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert [b"\x00\x01\xff"] == batch.column(0).to_pylist()
+
+
+def test_typed_null_parameter():
+    """
+    ``TypedNull`` should pass ``NULL`` with the given SQL type.
+    """
+    # Given
+    sql_integer = 4
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT ? AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=[TypedNull(sql_integer)],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.int32() == reader.schema.field("a").type
+    assert [None] == batch.column(0).to_pylist()
+
+
+def test_unsupported_parameter_type():
+    """
+    A parameter of an unsupported type should raise a ``TypeError`` before connecting.
+    """
+    # Given
+    parameters = ["Hello", object()]
+
+    # When
+    with raises(TypeError, match="Parameter 1 of type object is not supported"):
+        read_arrow_batches_from_odbc(
+            query="SELECT ?, ?",
+            batch_size=1,
+            connection_string="FOO",
+            parameters=parameters,
+        )
+
+
+def test_decimal_as_text():
+    """
+    Read a decimal column as the text reported by the driver.
+    """
+    # Given
+    table = "DecimalAsText"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a DECIMAL(5,2));"')
+    rows = "a\n123.45\n-0.10\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT a FROM {table}",
+        batch_size=10,
+        connection_string=MSSQL,
+        decimal_as_text=True,
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert batch.schema.field("a").type == pa.string()
+    assert ["123.45", "-.10"] == batch.column(0).to_pylist()
+
+
+def test_to_parquet(tmp_path):
+    """
+    Write the result set into a parquet file without passing the batches through Python.
+    """
+    # Given
+    table = "ToParquet"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int);"')
+    rows = "a\n1\n2\n3\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    query = f"SELECT a FROM {table}"
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=2, connection_string=MSSQL
+    )
+    path = str(tmp_path / "out.parquet")
+
+    # When
+    num_rows = reader.to_parquet(path, row_group_size=2)
+
+    # Then
+    assert 3 == num_rows
+    actual = pq.read_table(path).to_pydict()
+    assert {"a": [1, 2, 3]} == actual
+
+
+def test_to_parquet_with_empty_row_groups(tmp_path):
+    """
+    Row groups without any rows are rejected, rather than taking down the process.
+    """
+    # Given
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 42 AS a", batch_size=1, connection_string=MSSQL
+    )
+    path = str(tmp_path / "out.parquet")
+
+    # Then
+    with raises(ValueError, match="at least 1 row"):
+        reader.to_parquet(path, row_group_size=0)
+
+
+def test_to_csv(tmp_path):
+    """
+    Write the result set into a CSV file without passing the batches through Python.
+    """
+    # Given
+    table = "ToCsv"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int, b VARCHAR(10));"')
+    rows = "a,b\n1,one\n2,two\n3,three\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    query = f"SELECT a, b FROM {table}"
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=2, connection_string=MSSQL
+    )
+    path = tmp_path / "out.csv"
+
+    # When
+    num_rows = reader.to_csv(str(path), delimiter=";")
+
+    # Then
+    assert 3 == num_rows
+    assert "a;b\n1;one\n2;two\n3;three\n" == path.read_text()
+
+
+def test_connect_deadline():
+    """
+    Connecting to an unreachable server should give up once the deadline is exceeded.
+    """
+    # Given a non routable address, so the connection attempt hangs
+    unreachable = "Driver={ODBC Driver 17 for SQL Server};Server=10.255.255.1;UID=SA;PWD=x;"
+
+    # When / Then
+    with raises(Error, match="did not finish within 500ms"):
+        read_arrow_batches_from_odbc(
+            query="SELECT 1",
+            batch_size=1,
+            connection_string=unreachable,
+            connect_deadline_sec=0.5,
+        )
+
+
+def test_pair_columns_as_map():
+    """
+    Collapse a key and a value column into a single map column.
+    """
+    # Given
+    table = "PairColumnsAsMap"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int, k VARCHAR(10), v int);"'
+    )
+    rows = "id,k,v\n1,color,3\n2,,4\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=f"SELECT id, k, v FROM {table} ORDER BY id",
+        batch_size=10,
+        connection_string=MSSQL,
+        pair_columns_as_map=(1, 2),
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert ["id", "k"] == reader.schema.names
+    assert pa.types.is_map(reader.schema.field("k").type)
+    assert [[("color", 3)], None] == batch.column(1).to_pylist()
+
+
+def test_column_order():
+    """
+    Emit the columns in the order specified, rather than the order of the query.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a, 2 AS b, 3 AS c",
+        batch_size=1,
+        connection_string=MSSQL,
+        column_order=[2, 0],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert ["c", "a"] == reader.schema.names
+    assert {"c": [3], "a": [1]} == batch.to_pydict()
+
+
+def test_empty_string_parameter_is_not_null():
+    """
+    An empty string parameter must be bound as empty string, not as ``NULL``.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT CASE WHEN ? IS NULL THEN 'null' WHEN ? = '' THEN 'empty' END AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parameters=["", ""],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert {"a": ["empty"]} == batch.to_pydict()
+
+
+def test_parse_pg_arrays():
+    """
+    Parse text holding PostgreSQL array literals into lists. SQL Server has no arrays, so the
+    literals are selected as text.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=(
+            "SELECT a FROM (VALUES ('{1,2,3}'), ('{}'), ('{\"x y\",NULL,\"NULL\"}'), (NULL)) "
+            "AS t(a)"
+        ),
+        batch_size=10,
+        connection_string=MSSQL,
+        parse_pg_arrays=[0],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.list_(pa.string()) == reader.schema.field("a").type
+    expected = [["1", "2", "3"], [], ["x y", None, "NULL"], None]
+    assert expected == batch.column(0).to_pylist()
+
+
+def test_parse_pg_arrays_with_element_type():
+    """
+    Elements of parsed PostgreSQL arrays should be cast to the element type passed, so e.g. an
+    ``integer[]`` column is read as a list of integers.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT a FROM (VALUES ('{1,2,3}'), ('{NULL}'), ('{}')) AS t(a)",
+        batch_size=10,
+        connection_string=MSSQL,
+        parse_pg_arrays={0: pa.int32()},
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.list_(pa.int32()) == reader.schema.field("a").type
+    assert [[1, 2, 3], [None], []] == batch.column(0).to_pylist()
+
+
+def test_parse_nested_pg_arrays():
+    """
+    Nested PostgreSQL arrays are not supported and should be reported as an error.
+    """
+    # Given
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT '{{1,2},{3,4}}' AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parse_pg_arrays={0: pa.int32()},
+    )
+
+    # Then
+    with raises(Error, match="nested arrays are not supported"):
+        next(iter(reader))
+
+
+def test_schema_metadata():
+    """
+    Metadata attached to the schema should be visible in the batches.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        schema_metadata={"source": "SELECT 1 AS a"},
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert {b"source": b"SELECT 1 AS a"} == reader.schema.metadata
+    assert {b"source": b"SELECT 1 AS a"} == batch.schema.metadata
+
+
+def test_empty_schema_metadata():
+    """
+    Passing no metadata at all should leave the schema without metadata.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        schema_metadata={},
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert not batch.schema.metadata
+
+
+def test_bit_as_int8():
+    """
+    ``BIT`` columns are read as booleans by default, or as ``Int8`` if requested.
+    """
+    # Given
+    query = "SELECT CAST(1 AS BIT) AS a"
+
+    # When
+    as_boolean = read_arrow_batches_from_odbc(
+        query=query, batch_size=1, connection_string=MSSQL
+    )
+    as_int8 = read_arrow_batches_from_odbc(
+        query=query, batch_size=1, connection_string=MSSQL, bit_as_boolean=False
+    )
+
+    # Then
+    assert pa.bool_() == as_boolean.schema.field("a").type
+    assert pa.int8() == as_int8.schema.field("a").type
+    assert {"a": [1]} == next(iter(as_int8)).to_pydict()
+
+
+def test_constraint_violation():
+    """
+    Violating a primary key should raise a ``ConstraintViolation`` carrying the SQLSTATE.
+    """
+    # Given
+    table = "ConstraintViolation"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int PRIMARY KEY);"')
+    execute(query=f"INSERT INTO {table} (a) VALUES (1)", connection_string=MSSQL)
+
+    # When
+    with raises(ConstraintViolation) as error:
+        execute(query=f"INSERT INTO {table} (a) VALUES (1)", connection_string=MSSQL)
+
+    # Then
+    assert "23000" == error.value.sql_state()
+
+
+def test_syntax_error():
+    """
+    A malformed statement should raise a ``SyntaxOrAccessError``, which is also an ``Error``.
+    """
+    with raises(SyntaxOrAccessError):
+        read_arrow_batches_from_odbc(
+            query="SELECT FROM WHERE", batch_size=1, connection_string=MSSQL
+        )
+
+
+def test_read_with_isolated_environment():
+    """
+    Connections can be opened with an environment of their own, rather than the shared one.
+    """
+    # Given
+    environment = Environment()
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 42 AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        environment=environment,
+    )
+    # The reader keeps the environment alive
+    del environment
+    batch = next(iter(reader))
+
+    # Then
+    assert {"a": [42]} == batch.to_pydict()
+
+
+def test_stream_keeps_isolated_environment_alive():
+    """
+    A stream converted from a reader keeps the environment of its connection alive.
+    """
+    # Given
+    environment = Environment()
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 42 AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        environment=environment,
+    )
+
+    # When
+    stream = reader.into_pyarrow_record_batch_reader()
+    del reader
+    del environment
+    table = stream.read_all()
+
+    # Then
+    assert {"a": [42]} == table.to_pydict()
+
+
+def test_fixed_size_lists():
+    """
+    Read text holding vectors of numbers into fixed size lists, e.g. for embeddings.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT a FROM (VALUES ('[0.5,1,2]'), (NULL)) AS t(a)",
+        batch_size=10,
+        connection_string=MSSQL,
+        fixed_size_lists={0: (pa.float32(), 3)},
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.list_(pa.float32(), 3) == reader.schema.field("a").type
+    assert [[0.5, 1.0, 2.0], None] == batch.column(0).to_pylist()
+
+
+def test_insert_should_raise_on_invalid_connection_string():
+    """
+    Insert should raise on invalid connection string
+    """
+    # Given
+    invalid_connection_string = "FOO"
+    schema = pa.schema([("a", pa.int64())])
+
+    def iter_record_batches():
+        for i in range(2):
+            yield pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+
+    reader = pa.RecordBatchReader.from_batches(schema, iter_record_batches())
+
+    # When / Then
+    with raises(Error, match="Data source name not found"):
+        insert_into_table(
+            connection_string=invalid_connection_string,
+            chunk_size=20,
+            table="MyTable",
+            reader=reader,
+        )
+
+
+def test_insert_batches():
+    """
+    Insert data into database
+    """
+    # Given
+    table = "InsertBatches"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int IDENTITY(1,1), a BIGINT)"'
+    )
+    schema = pa.schema([("a", pa.int64())])
+
+    def iter_record_batches():
+        for i in range(2):
+            yield pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+
+    reader = pa.RecordBatchReader.from_batches(schema, iter_record_batches())
+
+    # When
+    insert_into_table(
+        connection_string=MSSQL, chunk_size=20, table=table, reader=reader
+    )
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+    )
+    assert "a\n1\n2\n3\n1\n2\n3\n" == actual.decode("utf8")
+
+
+def test_max_column_name_len():
+    """
+    SQL Server limits identifiers to 128 characters.
+    """
+    # When
+    actual = max_column_name_len(MSSQL)
+
+    # Then
+    assert 128 == actual
+
+
+def test_column_defaults():
+    """
+    Read the default value definitions of the columns of a table.
+    """
+    # Given
+    table = "ColumnDefaults"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a VARCHAR(10), b int DEFAULT 42);"'
+    )
+
+    # When
+    actual = column_defaults(table, MSSQL)
+
+    # Then
+    assert {"a": None, "b": "((42))"} == actual
+
+
+def test_column_defaults_within_schema():
+    """
+    Read the default value definitions of the columns of a table qualified with its schema.
+    """
+    # Given
+    table = "ColumnDefaultsWithinSchema"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int DEFAULT 7);"')
+
+    # When
+    actual = column_defaults(table, MSSQL, schema="dbo")
+
+    # Then
+    assert {"a": "((7))"} == actual
+
+
+def test_validate_connection_string():
+    """
+    A connection string referring to an installed driver is valid, even without connecting.
+    """
+    validate_connection_string(MSSQL)
+
+
+def test_validate_connection_string_with_unknown_driver():
+    """
+    Validation should report a driver which is not installed.
+    """
+    with raises(Error, match="Driver 'Does not exist' is not installed."):
+        validate_connection_string("Driver={Does not exist};Server=localhost;")
+
+
+def test_validate_connection_string_with_unterminated_brace():
+    """
+    Validation should report malformed connection strings.
+    """
+    with raises(Error, match="missing a closing"):
+        validate_connection_string("Driver={ODBC Driver 17 for SQL Server;Server=localhost;")
+
+
+def test_insert_batch_larger_than_chunk_size():
+    """
+    A single batch with more rows than fit into one chunk is split up by the writer. Nulls must
+    survive the split.
+    """
+    # Given
+    table = "InsertBatchLargerThanChunkSize"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int IDENTITY(1,1), a BIGINT)"'
+    )
+    schema = pa.schema([("a", pa.int64())])
+    batch = pa.RecordBatch.from_arrays(
+        [pa.array([1, None, 3, 4, None, 6, 7])], schema=schema
+    )
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    insert_into_table(connection_string=MSSQL, chunk_size=3, table=table, reader=reader)
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+    )
+    assert "a\n1\n\n3\n4\n\n6\n7\n" == actual.decode("utf8")
+
+
+def test_insert_into_view_with_instead_of_trigger():
+    """
+    Insert through an updatable view. The rows are routed through its ``INSTEAD OF`` trigger.
+    """
+    # Given
+    table = "InsertIntoViewBase"
+    view = "InsertIntoView"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP VIEW IF EXISTS {view};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int IDENTITY(1,1), a BIGINT)"'
+    )
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE VIEW {view} AS SELECT a FROM {table}"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TRIGGER {view}Insert ON {view} INSTEAD OF INSERT '
+        f'AS INSERT INTO {table} (a) SELECT a * 2 FROM inserted"'
+    )
+    schema = pa.schema([("a", pa.int64())])
+    batch = pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    insert_into_table(connection_string=MSSQL, chunk_size=20, table=view, reader=reader)
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+    )
+    assert "a\n2\n4\n6\n" == actual.decode("utf8")
+
+
+def test_insert_dictionary_encoded_column():
+    """
+    Dictionary encoded columns are decoded and inserted as plain text.
+    """
+    # Given
+    table = "InsertDictionaryEncoded"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int IDENTITY(1,1), a VARCHAR(10))"'
+    )
+    categories = pa.array(["red", "green", None, "red"]).dictionary_encode()
+    batch = pa.RecordBatch.from_arrays([categories], names=["a"])
+    reader = pa.RecordBatchReader.from_batches(batch.schema, [batch])
+
+    # When
+    insert_into_table(connection_string=MSSQL, chunk_size=20, table=table, reader=reader)
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+    )
+    assert "a\nred\ngreen\n\nred\n" == actual.decode("utf8")
+
+
+def test_column_names():
+    """
+    Columns should be named as specified, falling back to the names reported by the data source.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a, 2 AS b, 3 AS c",
+        batch_size=1,
+        connection_string=MSSQL,
+        column_names=["x", None, ""],
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert ["x", "b", "c"] == reader.schema.names
+    assert ["x", "b", "c"] == batch.schema.names
+
+
+def test_char_as_bool():
+    """
+    Single character flags should be read as booleans, with unknown characters being ``None``.
+    """
+    # Given
+    query = (
+        "SELECT CAST(a AS CHAR(1)) AS a FROM (VALUES ('Y'), ('N'), ('X'), (NULL)) AS flags(a)"
+    )
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=4, connection_string=MSSQL, char_as_bool={0: ("Y", "N")}
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.bool_() == reader.schema.field(0).type
+    assert [True, False, None, None] == batch.column(0).to_pylist()
+
+
+def test_arrow_c_stream():
+    """
+    Libraries supporting the Arrow PyCapsule interface should consume the reader directly.
+    """
+    # Given
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a UNION ALL SELECT 2", batch_size=1, connection_string=MSSQL
+    )
+
+    # When
+    table = pa.table(reader)
+
+    # Then
+    assert {"a": [1, 2]} == table.to_pydict()
+
+
+def test_arrow_c_stream_with_requested_schema():
+    """
+    Batches are cast to the schema requested by the consumer of the stream.
+    """
+    # Given
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT 1 AS a UNION ALL SELECT 2", batch_size=1, connection_string=MSSQL
+    )
+    requested = pa.schema([("a", pa.int64())])
+
+    # When
+    capsule = reader.__arrow_c_stream__(requested.__arrow_c_schema__())
+    table = pa.RecordBatchReader._import_from_c_capsule(capsule).read_all()
+
+    # Then
+    assert requested == table.schema
+    assert {"a": [1, 2]} == table.to_pydict()
+
+
+def test_insert_into_table_within_schema():
+    """
+    Insert into a table of a schema specified separately from the table name.
+    """
+    # Given
+    table = "InsertIntoSchema"
+    setup = (
+        "IF SCHEMA_ID('Analytics') IS NULL EXEC('CREATE SCHEMA Analytics');"
+        f"DROP TABLE IF EXISTS Analytics.{table};"
+        f"CREATE TABLE Analytics.{table} (a BIGINT);"
+    )
+    run(["odbcsv", "fetch", "-c", MSSQL, "-q", setup])
+    schema = pa.schema([("a", pa.int64())])
+    batch = pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    insert_into_table(
+        connection_string=MSSQL, chunk_size=20, table=table, schema="Analytics", reader=reader
+    )
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM Analytics.{table}"]
+    )
+    assert "a\n1\n2\n3\n" == actual.decode("utf8")
+
+
+def test_insert_into_table_within_quoted_schema():
+    """
+    Schema and table name are used verbatim, so they may be quoted by the caller.
+    """
+    # Given
+    table = "InsertIntoQuotedSchema"
+    setup = (
+        "IF SCHEMA_ID('Analytics') IS NULL EXEC('CREATE SCHEMA Analytics');"
+        f"DROP TABLE IF EXISTS Analytics.{table};"
+        f"CREATE TABLE Analytics.{table} (a BIGINT);"
+    )
+    run(["odbcsv", "fetch", "-c", MSSQL, "-q", setup])
+    schema = pa.schema([("a", pa.int64())])
+    batch = pa.RecordBatch.from_arrays([pa.array([1, 2, 3])], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    insert_into_table(
+        connection_string=MSSQL,
+        chunk_size=20,
+        table=f'"{table}"',
+        schema='"Analytics"',
+        reader=reader,
+    )
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM Analytics.{table}"]
+    )
+    assert "a\n1\n2\n3\n" == actual.decode("utf8")
+
+
+def test_execute_many():
+    """
+    Execute a parameterized update once for each row of the batches.
+    """
+    # Given
+    table = "ExecuteMany"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (id int, a int);"')
+    rows = "id,a\n1,10\n2,20\n3,30\n"
+    run(["odbcsv", "insert", "-c", MSSQL, table], input=rows, encoding="ascii")
+    schema = pa.schema([("a", pa.int32()), ("id", pa.int32())])
+    batch = pa.RecordBatch.from_arrays([pa.array([11, 33]), pa.array([1, 3])], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    execute_many(
+        reader=reader,
+        chunk_size=20,
+        query=f"UPDATE {table} SET a = ? WHERE id = ?",
+        connection_string=MSSQL,
+    )
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a FROM {table} ORDER BY id"]
+    )
+    assert "a\n11\n20\n33\n" == actual.decode("utf8")
+
+
+def test_empty_strings_as_null():
+    """
+    Empty strings should be read as ``None`` if requested, to match data sources like Oracle.
+    """
+    # Given
+    query = "SELECT a FROM (VALUES ('a'), (''), (NULL)) AS t(a)"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=3, connection_string=MSSQL, empty_strings_as_null=True
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert ["a", None, None] == batch.column(0).to_pylist()
+
+
+def test_raw_binary_columns():
+    """
+    Columns may be fetched as the raw bytes delivered by the driver, independent of their SQL type.
+    """
+    # Given
+    query = "SELECT CAST(1 AS INT) AS a, CAST(NULL AS INT) AS b"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=1, connection_string=MSSQL, raw_binary_columns=[0, 1]
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.binary() == reader.schema.field(0).type
+    # Little endian 32 bit integer
+    assert [b"\x01\x00\x00\x00"] == batch.column(0).to_pylist()
+    assert [None] == batch.column(1).to_pylist()
+
+
+def test_max_batch_rows():
+    """
+    No batch should hold more rows than the maximum specified, even after partitioning.
+    """
+    # Given
+    query = "SELECT a FROM (VALUES (1), (1), (1), (2)) AS t(a)"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=4, connection_string=MSSQL, partition_by=0, max_batch_rows=2
+    )
+    batches = [batch.column(0).to_pylist() for batch in reader]
+
+    # Then
+    assert [[1, 1], [1], [2]] == batches
+
+
+def test_transport_attributes():
+    """
+    Assemble the driver specific attributes selecting a transport.
+    """
+    # When
+    named_pipe = transport_attributes(Transport.SQL_SERVER_NAMED_PIPE, r"\\.\pipe\sql\query")
+    socket = transport_attributes(Transport.POSTGRES_UNIX_SOCKET, "/var/run/postgresql")
+
+    # Then
+    assert r"Server=np:\\.\pipe\sql\query;" == named_pipe
+    assert "Servername=/var/run/postgresql;" == socket
+
+
+def test_downcast_integers():
+    """
+    ``BIGINT`` columns should be narrowed per batch to the smallest type holding their values.
+    """
+    # Given
+    query = "SELECT a FROM (VALUES (CAST(1 AS BIGINT)), (300), (70000)) AS t(a)"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query, batch_size=1, connection_string=MSSQL, downcast_integers=True
+    )
+    types = [batch.schema.field(0).type for batch in reader]
+
+    # Then
+    assert [pa.int8(), pa.int16(), pa.int32()] == types
+
+
+def test_insert_null_type_column():
+    """
+    Columns of type ``Null`` should be inserted as ``NULL``, or raise if the target column is not
+    nullable.
+    """
+    # Given
+    table = "InsertNullType"
+    os.system(f'odbcsv fetch -c "{MSSQL}" -q "DROP TABLE IF EXISTS {table};"')
+    os.system(
+        f'odbcsv fetch -c "{MSSQL}" -q "CREATE TABLE {table} (a int NOT NULL, b VARCHAR(10))"'
+    )
+    schema = pa.schema([("a", pa.int32()), ("b", pa.null())])
+    batch = pa.RecordBatch.from_arrays([pa.array([1, 2]), pa.nulls(2)], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+
+    # When
+    insert_into_table(connection_string=MSSQL, chunk_size=20, table=table, reader=reader)
+
+    # Then
+    actual = check_output(
+        ["odbcsv", "fetch", "-c", MSSQL, "-q", f"SELECT a, b FROM {table} ORDER BY a"]
+    )
+    assert "a,b\n1,\n2,\n" == actual.decode("utf8")
+
+    # When / Then the target column is not nullable
+    schema = pa.schema([("a", pa.null())])
+    batch = pa.RecordBatch.from_arrays([pa.nulls(1)], schema=schema)
+    reader = pa.RecordBatchReader.from_batches(schema, [batch])
+    with raises(ConstraintViolation):
+        insert_into_table(connection_string=MSSQL, chunk_size=20, table=table, reader=reader)
+
+
+def test_progress_callback():
+    """
+    Progress should be reported each time another ``progress_every_rows`` rows have been fetched.
+    """
+    # Given
+    query = "SELECT a FROM (VALUES (1), (2), (3), (4), (5)) AS t(a)"
+    reported = []
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query,
+        batch_size=1,
+        connection_string=MSSQL,
+        on_progress=reported.append,
+        progress_every_rows=2,
+    )
+    for _ in reader:
+        pass
+
+    # Then
+    assert [2, 4] == reported
+
+
+def test_progress_callback_outlives_reader_converted_into_stream():
+    """
+    A stream converted from the reader keeps the progress callback alive, even after the
+    ``BatchReader`` itself has been garbage collected.
+    """
+    # Given
+    query = "SELECT a FROM (VALUES (1), (2), (3), (4), (5)) AS t(a)"
+    reported = []
+    reader = read_arrow_batches_from_odbc(
+        query=query,
+        batch_size=1,
+        connection_string=MSSQL,
+        on_progress=lambda total_rows: reported.append(total_rows),
+        progress_every_rows=2,
+    )
+
+    # When
+    stream = reader.into_pyarrow_record_batch_reader()
+    del reader
+    stream.read_all()
+
+    # Then
+    assert [2, 4] == reported
+
+
+def test_build_connection_string():
+    """
+    Placeholders should be replaced with escaped values, leaving other braces untouched.
+    """
+    # Given
+    template = "Driver={ODBC Driver 17 for SQL Server};Server={host};PWD={password};"
+
+    # When
+    connection_string = build_connection_string(
+        template, {"host": "localhost", "password": "secret;Trusted_Connection=yes"}
+    )
+
+    # Then
+    assert (
+        "Driver={ODBC Driver 17 for SQL Server};Server=localhost;"
+        "PWD={secret;Trusted_Connection=yes};"
+    ) == connection_string
+
+
+def test_build_connection_string_without_values():
+    """
+    A template without any values passed should be returned unchanged.
+    """
+    # Given
+    template = "Driver={ODBC Driver 17 for SQL Server};Server=localhost;"
+
+    # When
+    connection_string = build_connection_string(template, {})
+
+    # Then
+    assert template == connection_string
+
+
+def test_mask_columns():
+    """
+    Masked columns should only reveal hashes or ``None`` to Python.
+    """
+    # Given
+    query = "SELECT 'secret' AS a, 'secret' AS b, 'visible' AS c"
+
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query=query,
+        batch_size=1,
+        connection_string=MSSQL,
+        mask_columns={0: "sha256", 1: "redact"},
+    )
+    batch = next(iter(reader))
+
+    # Then
+    expected_hash = "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+    assert {"a": [expected_hash], "b": [None], "c": ["visible"]} == batch.to_pydict()
+
+
+def test_parse_pg_arrays_into_large_lists():
+    """
+    Parsed PostgreSQL arrays should use 64 Bit offsets if ``large_lists`` is set.
+    """
+    # When
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT '{a,NULL}' AS a",
+        batch_size=1,
+        connection_string=MSSQL,
+        parse_pg_arrays=[0],
+        large_lists=True,
+    )
+    batch = next(iter(reader))
+
+    # Then
+    assert pa.large_list(pa.large_string()) == reader.schema.field("a").type
+    assert [["a", None]] == batch.column(0).to_pylist()
+
+
+def test_retry_sql_states():
+    """
+    Errors with a SQLSTATE listed in ``retry_sql_states`` should be retried, and eventually raised
+    once ``max_retries`` is exhausted.
+    """
+    # Given
+    started = time.monotonic()
+
+    # When
+    with raises(SyntaxOrAccessError):
+        read_arrow_batches_from_odbc(
+            query="SELECT * FROM TableWhichDoesNotExist",
+            batch_size=1,
+            connection_string=MSSQL,
+            retry_sql_states=["42S02"],
+            max_retries=2,
+            retry_backoff_sec=0.1,
+        )
+
+    # Then: Backed off for 0.1 and 0.2 seconds.
+    assert time.monotonic() - started >= 0.3
+
+
+def test_next_and_is_last():
+    """
+    Report whether a batch is the last one of the result set.
+    """
+    # Given
+    reader = read_arrow_batches_from_odbc(
+        query="SELECT a FROM (VALUES (1), (2), (3)) AS t(a)",
+        batch_size=2,
+        connection_string=MSSQL,
+    )
+
+    # When
+    first = reader.next_and_is_last()
+    second = reader.next_and_is_last()
+    third = reader.next_and_is_last()
+
+    # Then
+    assert (first[0].num_rows, first[1]) == (2, False)
+    assert (second[0].num_rows, second[1]) == (1, True)
+    assert third is None
+
+
+def test_prepared_statement_executed_repeatedly():
+    """
+    Prepare a statement once and execute it with different parameters.
+    """
+    # Given
+    statement = prepare(query="SELECT ? AS a", connection_string=MSSQL)
+
+    # When
+    first = next(iter(statement.execute(batch_size=1, parameters=[1])))
+    second = next(iter(statement.execute(batch_size=1, parameters=[2])))
+
+    # Then
+    assert [1] == first.column(0).to_pylist()
+    assert [2] == second.column(0).to_pylist()
+
+
+def test_stream_of_prepared_statement_outlives_statement():
+    """
+    A stream converted from the reader of a prepared statement keeps the statement open. Executing
+    the statement again while the stream is alive raises an error.
+    """
+    # Given
+    statement = prepare(query="SELECT ? AS a", connection_string=MSSQL)
+    stream = statement.execute(batch_size=1, parameters=[1]).into_pyarrow_record_batch_reader()
+
+    # When
+    with raises(Error, match="still in use by the reader of its previous execution"):
+        statement.execute(batch_size=1, parameters=[2])
+    del statement
+    table = stream.read_all()
+
+    # Then
+    assert [1] == table.column(0).to_pylist()
+
+
+def test_reader_of_prepared_statement_stays_usable():
+    """
+    Executing a prepared statement while the reader of its previous execution is alive raises an
+    error, rather than invalidating that reader.
+    """
+    # Given
+    statement = prepare(query="SELECT ? AS a", connection_string=MSSQL)
+    reader = statement.execute(batch_size=1, parameters=[1])
+
+    # When
+    with raises(Error, match="still in use by the reader of its previous execution"):
+        statement.execute(batch_size=1, parameters=[2])
+    batch = next(iter(reader))
+
+    # Then
+    assert [1] == batch.column(0).to_pylist()